@@ -0,0 +1,120 @@
+//! Blocking wrapper around [`Arweave`], for callers that don't run an async runtime.
+
+use std::path::PathBuf;
+
+use pretend::StatusCode;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::{
+    crypto::base64::Base64,
+    error::Error,
+    transaction::{tags::Tag, Tx},
+    types::{NetworkInfo, TxStatus},
+    Arweave,
+};
+
+/// Runs the calls of an [`Arweave`] client to completion on a dedicated single-threaded
+/// runtime, the same way `reqwest::blocking` does.
+///
+/// Must not be used, or dropped, from within an async runtime.
+pub struct BlockingArweave {
+    inner: Arweave,
+    runtime: Runtime,
+}
+
+impl BlockingArweave {
+    pub fn new(arweave: Arweave) -> Result<Self, Error> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        Ok(BlockingArweave {
+            inner: arweave,
+            runtime,
+        })
+    }
+
+    /// The wrapped async client, e.g. for the methods that don't need the network.
+    pub fn inner(&self) -> &Arweave {
+        &self.inner
+    }
+
+    pub fn network_info(&self) -> Result<NetworkInfo, Error> {
+        self.runtime.block_on(self.inner.network_info())
+    }
+
+    pub fn get_balance(&self, address: &str) -> Result<String, Error> {
+        self.runtime.block_on(self.inner.get_balance(address))
+    }
+
+    pub fn get_fee(&self, target: Base64, data: Vec<u8>) -> Result<u64, Error> {
+        self.runtime.block_on(self.inner.get_fee(target, data))
+    }
+
+    pub fn get_tx(&self, id: Base64) -> Result<(StatusCode, Option<Tx>), Error> {
+        self.runtime.block_on(self.inner.get_tx(id))
+    }
+
+    pub fn get_tx_status(&self, id: Base64) -> Result<(StatusCode, Option<TxStatus>), Error> {
+        self.runtime.block_on(self.inner.get_tx_status(id))
+    }
+
+    pub fn post_transaction(&self, signed_transaction: &Tx) -> Result<(String, u64), Error> {
+        self.runtime
+            .block_on(self.inner.post_transaction(signed_transaction))
+    }
+
+    pub fn transfer(
+        &self,
+        target: Base64,
+        quantity: u128,
+        fee: u64,
+    ) -> Result<(String, u64), Error> {
+        self.runtime
+            .block_on(self.inner.transfer(target, quantity, fee))
+    }
+
+    pub fn upload_file_from_path(
+        &self,
+        file_path: PathBuf,
+        additional_tags: Vec<Tag<Base64>>,
+        fee: u64,
+    ) -> Result<(String, u64), Error> {
+        self.runtime.block_on(
+            self.inner
+                .upload_file_from_path(file_path, additional_tags, fee),
+        )
+    }
+
+    pub fn download_tx_data_to_file(&self, id: Base64, path: PathBuf) -> Result<(), Error> {
+        self.runtime
+            .block_on(self.inner.download_tx_data_to_file(id, path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use httpmock::{Method::GET, MockServer};
+
+    use crate::Arweave;
+
+    use super::BlockingArweave;
+
+    #[test]
+    fn test_blocking_get_balance() {
+        let address = "PAgdonEn9f5xd-UbYdCX40Sj28eltQVnxz6bbUijeVY";
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/wallet/{}/balance", address));
+            then.status(200).body("1000");
+        });
+
+        let url = url::Url::parse(&server.url("/")).unwrap();
+        let arweave =
+            Arweave::from_keypair_path(PathBuf::from("res/test_wallet.json"), url).unwrap();
+        let arweave = BlockingArweave::new(arweave).unwrap();
+
+        assert_eq!(arweave.get_balance(address).unwrap(), "1000");
+        mock.assert();
+    }
+}
@@ -6,12 +6,45 @@ use std::mem;
 
 use async_trait::async_trait;
 use pretend::{client::Bytes, Error, HeaderMap, Response, Result};
-use reqwest::Method;
+use reqwest::{redirect, Method};
 use url::Url;
 
-#[derive(Default)]
+use crate::consts::MAX_REDIRECTS;
+
+/// Builds the reqwest client used by default by every client in the crate, so that they
+/// all handle redirects and compression the same way.
+///
+/// Sends `Accept-Encoding: gzip` and transparently decompresses gzipped responses, which
+/// saves a lot of bandwidth on large JSON responses such as blocks, and follows up to
+/// [`MAX_REDIRECTS`] redirects.
+pub fn http_client() -> reqwest::Client {
+    http_client_builder()
+        .build()
+        .expect("failed to build reqwest client")
+}
+
+/// Same settings as [`http_client`], for callers that need to tune the client further, e.g.
+/// its connection pool.
+pub fn http_client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .gzip(true)
+        .redirect(redirect::Policy::limited(MAX_REDIRECTS))
+}
+
 pub struct Client(reqwest::Client);
 
+impl Default for Client {
+    fn default() -> Self {
+        Self(http_client())
+    }
+}
+
+impl From<reqwest::Client> for Client {
+    fn from(client: reqwest::Client) -> Self {
+        Self(client)
+    }
+}
+
 #[async_trait]
 impl pretend::client::Client for Client {
     async fn execute(
@@ -37,3 +70,34 @@ impl pretend::client::Client for Client {
         Ok(Response::new(status, headers, bytes))
     }
 }
+
+/// Makes sure `url` ends with `/`. `Url::join` replaces the last path segment of a url
+/// without a trailing slash, so `https://host/arweave` joined with `tx` would otherwise
+/// become `https://host/tx`.
+pub fn normalize_base_url(mut url: Url) -> Url {
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::normalize_base_url;
+
+    #[test]
+    fn test_normalize_base_url() {
+        for (base, expected) in [
+            ("https://arweave.net", "https://arweave.net/tx_anchor"),
+            ("https://arweave.net/", "https://arweave.net/tx_anchor"),
+            ("https://host/arweave", "https://host/arweave/tx_anchor"),
+            ("https://host/arweave/", "https://host/arweave/tx_anchor"),
+        ] {
+            let url = normalize_base_url(Url::parse(base).unwrap());
+            assert_eq!(url.join("tx_anchor").unwrap().as_str(), expected);
+        }
+    }
+}
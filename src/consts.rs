@@ -2,6 +2,13 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub const ARWEAVE_BASE_URL: &str = "https://arweave.net/";
 
+/// Port Arweave nodes listen on unless configured otherwise.
+pub const ARWEAVE_NODE_PORT: u16 = 1984;
+
+/// Oracle used to fetch the AR/USD price.
+pub const ORACLE_URL: &str =
+    "https://api.coingecko.com/api/v3/simple/price?ids=arweave&vs_currencies=usd";
+
 /// Block size used for pricing calculations = 256 KB
 pub const BLOCK_SIZE: u64 = 1024 * 256;
 
@@ -18,6 +25,57 @@ pub const CHUNKS_RETRIES: u16 = 10;
 /// Number of seconds to wait between retying to post a failed chunk.
 pub const CHUNKS_RETRY_SLEEP: u64 = 1;
 
+/// Number of chunk retries allowed across a whole upload before giving up, so that an
+/// unavailable gateway fails the upload quickly instead of every chunk retrying on its own.
+pub const CHUNKS_RETRY_BUDGET: usize = 100;
+
+/// Default number of confirmations after which a transaction is considered final.
+/// Arweave transactions are generally regarded as final after ~15-50 confirmations,
+/// depending on how much risk of a chain reorganisation the application can tolerate.
+pub const CONFIRMATION_THRESHOLD: u64 = 15;
+
+/// Number of seconds to wait before polling the status of a transaction again.
+/// Doubles after every poll up to [`CONFIRMATION_POLL_MAX_SLEEP`].
+pub const CONFIRMATION_POLL_SLEEP: u64 = 10;
+
+/// Maximum number of seconds to wait between polling the status of a transaction.
+pub const CONFIRMATION_POLL_MAX_SLEEP: u64 = 60;
+
+/// Number of seconds to wait for a peer to answer a single download request.
+pub const DOWNLOAD_PEER_TIMEOUT: u64 = 30;
+
+/// Number of failed chunk requests after which a peer is no longer used for a download.
+pub const DOWNLOAD_PEER_MAX_FAILURES: usize = 3;
+
+/// `App-Name` tag of split uploads that don't set their own.
+pub const SPLIT_UPLOAD_APP_NAME: &str = "arweave-rs";
+
+/// Number of blocks a block anchor stays valid for. Transactions anchored to an older block
+/// are rejected by the nodes.
+pub const ANCHOR_MAX_AGE_BLOCKS: u64 = 50;
+
+/// First element of the deep hash signed by `Arweave::sign_message_with_context`, so that
+/// these signatures can't be mistaken for transaction signatures.
+pub const MESSAGE_SIGNATURE_PREFIX: &str = "arweave-rs-message";
+
+/// Tag holding the data root of the transaction data, used to find earlier uploads of the
+/// same data through GraphQL.
+pub const DATA_ROOT_TAG: &str = "Data-Root";
+
+/// Number of transactions fetched at the same time when walking the transactions of a block.
+pub const BLOCK_TXS_CONCURRENCY: usize = 10;
+
+/// Number of peers checked at the same time when looking for healthy peers to download from.
+pub const PEER_HEALTH_CHECK_CONCURRENCY: usize = 10;
+
+/// Number of seconds to wait for a peer to answer a health check before treating it as
+/// unhealthy.
+pub const PEER_HEALTH_CHECK_TIMEOUT: u64 = 5;
+
+/// Maximum number of redirects followed by the http clients, e.g. from a mirror to the
+/// canonical gateway host.
+pub const MAX_REDIRECTS: usize = 10;
+
 // First block to use V2 block format
 pub const V2_BLOCK_HEIGHT: u32 = 269510;
 
@@ -1,5 +1,6 @@
 use std::str::FromStr;
 
+use rsa::BigUint;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::error::Error;
@@ -43,6 +44,16 @@ impl Base64 {
     pub fn empty() -> Self {
         Base64(vec![])
     }
+
+    /// Encodes `n` as big-endian bytes, the way Arweave encodes RSA moduli and exponents.
+    pub fn from_biguint(n: &BigUint) -> Self {
+        Base64(n.to_bytes_be())
+    }
+
+    /// Decodes the bytes as a big-endian unsigned integer.
+    pub fn to_biguint(&self) -> BigUint {
+        BigUint::from_bytes_be(&self.0)
+    }
 }
 
 impl Serialize for Base64 {
@@ -74,7 +85,9 @@ impl<'de> Deserialize<'de> for Base64 {
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use std::{fs, str::FromStr};
+
+    use rsa::BigUint;
 
     use crate::crypto::base64::Base64;
 
@@ -106,4 +119,18 @@ mod tests {
         let foo_b64 = Base64(vec![44; 7]);
         assert_eq!(foo_b64.to_string(), "LCwsLCwsLA".to_string());
     }
+
+    #[test]
+    fn test_base64_convert_biguint() {
+        let exponent = Base64::from_str("AQAB").unwrap();
+        assert_eq!(exponent.to_biguint(), BigUint::from(65537u32));
+        assert_eq!(Base64::from_biguint(&BigUint::from(65537u32)), exponent);
+
+        let jwk: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string("res/test_wallet.json").unwrap()).unwrap();
+        let modulus = Base64::from_str(jwk["n"].as_str().unwrap()).unwrap();
+        let n = modulus.to_biguint();
+        assert_eq!(n.bits(), 2048);
+        assert_eq!(Base64::from_biguint(&n), modulus);
+    }
 }
@@ -1,9 +1,14 @@
 //! Functionality for chunking file data and calculating and verifying root ids.
 
-use crate::error::Error;
+use std::ops::Range;
+
+use crate::{error::Error, types::Chunk};
 use borsh::BorshDeserialize;
 
-use super::hash::{hash_all_sha256, sha256};
+use super::{
+    base64::Base64,
+    hash::{hash_all_sha256, sha256},
+};
 
 /// Single struct used for original data chunks (Leaves) and branch nodes (hashes of pairs of child nodes).
 #[derive(Debug, PartialEq, Clone)]
@@ -93,7 +98,7 @@ pub fn generate_leaves(data: Vec<u8>) -> Result<Vec<Node>, Error> {
 
     if data_chunks.len() > 1 && data_chunks.last().unwrap().len() < MIN_CHUNK_SIZE {
         last_two = data_chunks.split_off(data_chunks.len() - 2).concat();
-        let chunk_size = last_two.len() / 2 + (last_two.len() % 2 != 0) as usize;
+        let chunk_size = last_two.len().div_ceil(2);
         data_chunks.append(&mut last_two.chunks(chunk_size).collect::<Vec<&[u8]>>());
     }
 
@@ -122,6 +127,17 @@ pub fn generate_leaves(data: Vec<u8>) -> Result<Vec<Node>, Error> {
     Ok(leaves)
 }
 
+/// Number of leaves [`generate_leaves`] produces for `data_len` bytes of data, without
+/// chunking the data. Rebalancing a small last chunk doesn't change the count, and data
+/// that is a multiple of [`MAX_CHUNK_SIZE`] gets a trailing empty chunk, so any non-empty
+/// data has `data_len / MAX_CHUNK_SIZE + 1` chunks.
+pub fn chunk_count(data_len: usize) -> usize {
+    match data_len {
+        0 => 0,
+        len => len / MAX_CHUNK_SIZE + 1,
+    }
+}
+
 /// Hashes together a single branch node from a pair of child nodes.
 pub fn hash_branch(left: Node, right: Node) -> Result<Node, Error> {
     let max_byte_range = left.max_byte_range.to_note_vec();
@@ -138,7 +154,7 @@ pub fn hash_branch(left: Node, right: Node) -> Result<Node, Error> {
 
 /// Builds one layer of branch nodes from a layer of child nodes.
 pub fn build_layer(nodes: Vec<Node>) -> Result<Vec<Node>, Error> {
-    let mut layer = Vec::<Node>::with_capacity(nodes.len() / 2 + (nodes.len() % 2 != 0) as usize);
+    let mut layer = Vec::<Node>::with_capacity(nodes.len().div_ceil(2));
     let mut nodes_iter = nodes.into_iter();
     while let Some(left) = nodes_iter.next() {
         if let Some(right) = nodes_iter.next() {
@@ -204,6 +220,91 @@ pub fn resolve_proofs(node: Node, proof: Option<Proof>) -> Result<Vec<Proof>, Er
     }
 }
 
+/// Builds the tree for `data` and returns the [`Chunk`] containing the byte at `offset`
+/// together with its [`Proof`], without resolving proofs for other chunks.
+pub fn proof_for_offset(data: &[u8], offset: usize) -> Result<(Chunk, Proof), Error> {
+    if offset >= data.len() {
+        return Err(Error::OffsetOutOfRange(offset, data.len()));
+    }
+
+    let mut node = generate_data_root(generate_leaves(data.to_vec())?)?;
+    let data_root = Base64(node.id.to_vec());
+    let mut proof = Vec::new();
+    loop {
+        match node {
+            // Branch
+            Node {
+                min_byte_range,
+                left_child: Some(left_child),
+                right_child: Some(right_child),
+                ..
+            } => {
+                proof.extend(left_child.id);
+                proof.extend(right_child.id);
+                proof.extend(min_byte_range.to_note_vec());
+                node = match offset < min_byte_range {
+                    true => *left_child,
+                    false => *right_child,
+                };
+            }
+            // Leaf
+            Node {
+                data_hash: Some(data_hash),
+                max_byte_range,
+                ..
+            } => {
+                proof.extend(data_hash);
+                proof.extend(max_byte_range.to_note_vec());
+                let proof = Proof {
+                    offset: max_byte_range - 1,
+                    proof,
+                };
+                let chunk =
+                    Chunk::build(data_root, data, node.min_byte_range, max_byte_range, &proof);
+                return Ok((chunk, proof));
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Validates `chunk` and the `data_path` served with it by `/chunk/{offset}` against the
+/// `root_id` of the transaction, walking the whole path down to the leaf of the chunk.
+/// Returns the byte range of the chunk within the transaction data.
+pub fn validate_chunk_path(
+    root_id: [u8; HASH_SIZE],
+    data_path: &[u8],
+    chunk: &[u8],
+) -> Result<Range<usize>, Error> {
+    let leaf_start = data_path
+        .len()
+        .checked_sub(HASH_SIZE + NOTE_SIZE)
+        .ok_or(Error::InvalidProof)?;
+    if chunk.is_empty() || leaf_start % (HASH_SIZE * 2 + NOTE_SIZE) != 0 {
+        return Err(Error::InvalidProof);
+    }
+
+    let max_byte_range = LeafProof::try_from_proof_slice(&data_path[leaf_start..])?.offset();
+    let min_byte_range = max_byte_range
+        .checked_sub(chunk.len())
+        .ok_or(Error::InvalidProof)?;
+    let data_hash = sha256(chunk);
+    let leaf = Node {
+        id: hash_all_sha256(vec![&data_hash, &max_byte_range.to_note_vec()]),
+        data_hash: Some(data_hash),
+        min_byte_range,
+        max_byte_range,
+        left_child: None,
+        right_child: None,
+    };
+    let proof = Proof {
+        offset: max_byte_range - 1,
+        proof: data_path.to_vec(),
+    };
+    validate_chunk(root_id, leaf, proof)?;
+    Ok(min_byte_range..max_byte_range)
+}
+
 /// Validates chunk of data against provided [`Proof`].
 pub fn validate_chunk(
     mut root_id: [u8; HASH_SIZE],
@@ -253,7 +354,7 @@ pub fn validate_chunk(
 
             // Validate leaf: both id and data_hash are correct.
             let id = hash_all_sha256(vec![&data_hash, &max_byte_range.to_note_vec()]);
-            if id != root_id && data_hash != leaf_proof.data_hash {
+            if id != root_id || data_hash != leaf_proof.data_hash {
                 return Err(Error::InvalidProof);
             }
         }
@@ -445,4 +546,164 @@ mod tests {
         assert_eq!(131072, leaves[1].max_byte_range - leaves[1].min_byte_range);
         Ok(())
     }
+
+    fn assert_root_and_ranges(size: usize, root_actual: &str, ranges: &[(usize, usize)]) {
+        let leaves: Vec<Node> = generate_leaves(vec![0; size]).unwrap();
+        assert_eq!(
+            leaves
+                .iter()
+                .map(|leaf| (leaf.min_byte_range, leaf.max_byte_range))
+                .collect::<Vec<_>>(),
+            ranges
+        );
+        let root = generate_data_root(leaves).unwrap();
+        assert_eq!(Base64(root.id.to_vec()).to_string(), root_actual);
+    }
+
+    // roots and byte ranges as calculated by arweave-js
+    #[test]
+    fn test_rebalancing_boundaries() {
+        assert_root_and_ranges(
+            MAX_CHUNK_SIZE + 1,
+            "br1Vtl3TS_NGWdHmYqBh3-MxrlckoluHCZGmUZk-dJc",
+            &[(0, 131073), (131073, 262145)],
+        );
+        assert_root_and_ranges(
+            MAX_CHUNK_SIZE + MIN_CHUNK_SIZE - 1,
+            "wyvwYFO5SDtwHx8zHWsxbQmWZgHRI5geZ3g4vcQBZVo",
+            &[(0, 147456), (147456, 294911)],
+        );
+        assert_root_and_ranges(
+            MAX_CHUNK_SIZE + MIN_CHUNK_SIZE,
+            "0nSiWfIOVNq8U7Ynp0pO0y_cfqfKP1C7-N4LNjKzm_M",
+            &[(0, 262144), (262144, 294912)],
+        );
+        assert_root_and_ranges(
+            2 * MAX_CHUNK_SIZE,
+            "zJuJu7Dwmaj6mkfzgUIBtWC34UwdfhK6XXv_WqvC25Y",
+            &[(0, 262144), (262144, 524288), (524288, 524288)],
+        );
+        assert_root_and_ranges(
+            2 * MAX_CHUNK_SIZE + 1,
+            "OcKXGX2UpK8ovqPRbDXC8ZXN8jhNldK7CG4p-bxTc-M",
+            &[(0, 262144), (262144, 393217), (393217, 524289)],
+        );
+    }
+
+    #[tokio::test]
+    async fn test_proof_for_offset() -> Result<(), Error> {
+        let data = fs::read(ONE_MB_BIN).await.unwrap();
+        let leaves: Vec<Node> = generate_leaves(data.clone()).unwrap();
+        let root = generate_data_root(leaves.clone()).unwrap();
+        let root_id = root.id;
+        let proofs = resolve_proofs(root, None).unwrap();
+
+        for offset in [0, MAX_CHUNK_SIZE - 1, MAX_CHUNK_SIZE, data.len() - 1] {
+            let (chunk, proof) = proof_for_offset(&data, offset).unwrap();
+            let idx = leaves
+                .iter()
+                .position(|leaf| leaf.min_byte_range <= offset && offset < leaf.max_byte_range)
+                .unwrap();
+            let leaf = &leaves[idx];
+            assert_eq!(chunk.data_root.0, root_id);
+            assert_eq!(
+                chunk.chunk.0,
+                data[leaf.min_byte_range..leaf.max_byte_range]
+            );
+            assert_eq!(chunk.data_path.0, proof.proof);
+            assert_eq!(proof, proofs[idx]);
+            assert!(validate_chunk(root_id, leaf.clone(), proof).is_ok());
+        }
+
+        assert!(matches!(
+            proof_for_offset(&data, data.len()),
+            Err(Error::OffsetOutOfRange(_, _))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_validate_chunk_tampered_leaf() -> Result<(), Error> {
+        let data = fs::read(ONE_MB_BIN).await.unwrap();
+        let leaves: Vec<Node> = generate_leaves(data).unwrap();
+        let root = generate_data_root(leaves.clone()).unwrap();
+        let root_id = root.id;
+        let mut proofs = resolve_proofs(root, None).unwrap();
+
+        // The branches still lead to the chunk, only the data hash in the leaf is wrong.
+        let proof = &mut proofs[1].proof;
+        let leaf = proof.len() - HASH_SIZE - NOTE_SIZE;
+        proof[leaf] ^= 1;
+        assert!(matches!(
+            validate_chunk(root_id, leaves[1].clone(), proofs[1].clone()),
+            Err(Error::InvalidProof)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_validate_chunk_path() -> Result<(), Error> {
+        let data = fs::read(ONE_MB_BIN).await.unwrap();
+        let leaves: Vec<Node> = generate_leaves(data.clone()).unwrap();
+        let root = generate_data_root(leaves.clone()).unwrap();
+        let root_id = root.id;
+        let proofs = resolve_proofs(root, None).unwrap();
+
+        for (leaf, proof) in leaves.iter().zip(&proofs) {
+            let chunk = &data[leaf.min_byte_range..leaf.max_byte_range];
+            if chunk.is_empty() {
+                continue;
+            }
+            assert_eq!(
+                validate_chunk_path(root_id, &proof.proof, chunk)?,
+                leaf.min_byte_range..leaf.max_byte_range
+            );
+        }
+
+        // A chunk with a path of its own, valid for a tree of just that chunk.
+        let chunk = &data[..leaves[0].max_byte_range];
+        let own_root = generate_data_root(generate_leaves(chunk.to_vec())?)?;
+        let own_path = resolve_proofs(own_root.clone(), None)?.remove(0).proof;
+        assert!(validate_chunk_path(own_root.id, &own_path, chunk).is_ok());
+        assert!(matches!(
+            validate_chunk_path(root_id, &own_path, chunk),
+            Err(Error::InvalidProof)
+        ));
+        assert!(matches!(
+            validate_chunk_path(root_id, &proofs[1].proof, chunk),
+            Err(Error::InvalidProof)
+        ));
+        assert!(matches!(
+            validate_chunk_path(root_id, &proofs[0].proof[1..], chunk),
+            Err(Error::InvalidProof)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_chunk_count() -> Result<(), Error> {
+        let data = fs::read(ONE_MB_BIN).await.unwrap();
+        assert_eq!(
+            chunk_count(data.len()),
+            generate_leaves(data).unwrap().len()
+        );
+
+        for size in [
+            1,
+            MIN_CHUNK_SIZE,
+            MAX_CHUNK_SIZE - 1,
+            MAX_CHUNK_SIZE,
+            MAX_CHUNK_SIZE + 1,
+            MAX_CHUNK_SIZE + MIN_CHUNK_SIZE,
+            2 * MAX_CHUNK_SIZE,
+            3 * MAX_CHUNK_SIZE + 17,
+        ] {
+            assert_eq!(
+                chunk_count(size),
+                generate_leaves(vec![0; size]).unwrap().len()
+            );
+        }
+        assert_eq!(chunk_count(0), 0);
+        Ok(())
+    }
 }
@@ -15,6 +15,7 @@ pub mod sign;
 pub mod utils;
 pub mod verify;
 
+#[derive(Clone, Debug)]
 pub struct Provider {
     pub signer: Box<Signer>,
 }
@@ -62,6 +63,7 @@ mod tests {
 
     use super::{base64::Base64, Provider};
 
+    #[allow(clippy::derivable_impls)]
     impl Default for Provider {
         fn default() -> Self {
             Self {
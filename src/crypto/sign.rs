@@ -2,14 +2,17 @@
 
 use crate::error::Error;
 use jsonwebkey as jwk;
-use rand::thread_rng;
-use rsa::{pkcs8::DecodePrivateKey, PaddingScheme, PublicKeyParts, RsaPrivateKey};
+use rsa::{pkcs8::DecodePrivateKey, PublicKeyParts, RsaPrivateKey};
 use sha2::Digest;
-use std::{fs, path::PathBuf};
+use std::{fmt, fs, path::PathBuf};
 
-use super::base64::Base64;
+use super::{
+    base64::Base64,
+    utils::{PssParams, PSS_PARAMS},
+};
 
 /// Struct for for crypto methods.
+#[derive(Clone)]
 pub struct Signer {
     priv_key: RsaPrivateKey,
 }
@@ -49,26 +52,32 @@ impl Signer {
     }
 
     pub fn sign(&self, message: &[u8]) -> Result<Base64, Error> {
+        self.sign_with_params(message, &PSS_PARAMS)
+    }
+
+    pub fn sign_with_params(&self, message: &[u8], params: &PssParams) -> Result<Base64, Error> {
         let mut hasher = sha2::Sha256::new();
         hasher.update(message);
         let hashed = hasher.finalize();
 
-        let rng = thread_rng();
-        let padding = PaddingScheme::PSS {
-            salt_rng: Box::new(rng),
-            digest: Box::new(sha2::Sha256::new()),
-            salt_len: None,
-        };
-
         let signature = self
             .priv_key
-            .sign(padding, &hashed)
+            .sign(params.padding_scheme(), &hashed)
             .map_err(|e| Error::SigningError(e.to_string()))?;
 
         Ok(Base64(signature))
     }
 }
 
+/// Only shows the wallet address, so that the private key can't end up in logs.
+impl fmt::Debug for Signer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Signer")
+            .field("address", &self.wallet_address().to_string())
+            .finish_non_exhaustive()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{path::PathBuf, str::FromStr};
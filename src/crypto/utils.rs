@@ -1,3 +1,8 @@
+use data_encoding::BASE64URL;
+use rand::thread_rng;
+use rsa::PaddingScheme;
+use sha2::Digest;
+
 pub fn copy_into_slice_32(m: &[u8]) -> [u8; 32] {
     let mut result: [u8; 32] = [0; 32];
     result.copy_from_slice(m);
@@ -15,3 +20,32 @@ pub fn concat_u8_48(left: [u8; 48], right: [u8; 48]) -> [u8; 96] {
     let mut iter = left.into_iter().chain(right);
     [(); 96].map(|_| iter.next().expect("Could not get concat two arrays"))
 }
+
+/// Formats an RSA modulus as a public JWK with Arweave's fixed public exponent.
+pub fn public_jwk_string(modulus: &[u8]) -> String {
+    format!(
+        "{{\"kty\":\"RSA\",\"e\":\"AQAB\",\"n\":\"{}\"}}",
+        BASE64URL.encode(modulus)
+    )
+}
+
+/// RSA-PSS parameters shared by signing and verification, so that both always agree on
+/// the salt length. With `salt_len: None` signatures use the maximum salt length for the
+/// key and verification detects the salt length from the signature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PssParams {
+    pub salt_len: Option<usize>,
+}
+
+/// PSS parameters used for Arweave signatures.
+pub const PSS_PARAMS: PssParams = PssParams { salt_len: None };
+
+impl PssParams {
+    pub fn padding_scheme(&self) -> PaddingScheme {
+        PaddingScheme::PSS {
+            salt_rng: Box::new(thread_rng()),
+            digest: Box::new(sha2::Sha256::new()),
+            salt_len: self.salt_len,
+        }
+    }
+}
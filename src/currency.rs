@@ -8,6 +8,10 @@ use crate::error::Error;
 /// Winstons are a sub unit of the native Arweave network token, AR. There are 10<sup>12</sup> Winstons per AR.
 pub const WINSTONS_PER_AR: u64 = 1_000_000_000_000;
 
+/// Upper bound of the AR supply (55M genesis + 11M mining rewards) in Winstons. No valid
+/// transaction can transfer more than this.
+pub const MAX_SUPPLY_WINSTONS: u128 = 66_000_000 * WINSTONS_PER_AR as u128;
+
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub struct Currency {
     arweave: u64, //integer
@@ -31,34 +35,56 @@ impl From<u128> for Currency {
     }
 }
 
+impl Currency {
+    /// Formats the amount in whole AR with all 12 decimal places, e.g. `1.500000000000`.
+    pub fn to_ar_string(&self) -> String {
+        format!("{}.{:012}", self.arweave, self.winston)
+    }
+}
+
 impl FromStr for Currency {
     type Err = Error;
 
+    /// Parses either a whole number of winstons or an AR amount with at most 12 decimal
+    /// places. More decimal places can't be represented in winstons and are rejected, as
+    /// are amounts above [`MAX_SUPPLY_WINSTONS`].
     fn from_str(s: &str) -> Result<Self, Error> {
-        let split: Vec<&str> = s.split('.').collect();
-        if split.len() == 2 {
-            Ok(Currency {
-                arweave: split[0].parse::<u64>().map_err(Error::ParseIntError)?,
-                winston: split[1].parse::<u64>().map_err(Error::ParseIntError)?,
-            })
-        } else {
-            Ok(Currency {
-                winston: split[0].parse::<u64>().map_err(Error::ParseIntError)?,
-                ..Currency::default()
-            })
+        let currency = match s.split_once('.') {
+            Some((arweave, winston)) => {
+                if winston.len() > 12 || !winston.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(Error::InvalidAmount(s.to_owned()));
+                }
+                Currency {
+                    arweave: arweave.parse::<u64>()?,
+                    winston: format!("{:0<12}", winston).parse::<u64>()?,
+                }
+            }
+            None => {
+                let winstons = s.parse::<u128>()?;
+                if winstons > MAX_SUPPLY_WINSTONS {
+                    return Err(Error::InvalidAmount(s.to_owned()));
+                }
+                Currency::from(winstons)
+            }
+        };
+        if currency.arweave as u128 * WINSTONS_PER_AR as u128 + currency.winston as u128
+            > MAX_SUPPLY_WINSTONS
+        {
+            return Err(Error::InvalidAmount(s.to_owned()));
         }
+        Ok(currency)
     }
 }
 
-impl ToString for Currency {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let decimal = format!("{:#012}", self.winston);
         if self.arweave == 0 && self.winston == 0 {
-            '0'.to_string()
+            write!(f, "0")
         } else if self.arweave == 0 {
-            decimal.trim_start_matches('0').to_string()
+            write!(f, "{}", decimal.trim_start_matches('0'))
         } else {
-            self.arweave.to_string() + &decimal
+            write!(f, "{}{}", self.arweave, decimal)
         }
     }
 }
@@ -83,7 +109,8 @@ impl<'de> Deserialize<'de> for Currency {
 mod tests {
     use std::str::FromStr;
 
-    use super::Currency;
+    use super::{Currency, MAX_SUPPLY_WINSTONS};
+    use crate::error::Error;
 
     #[test]
     fn test_str_parse() {
@@ -118,6 +145,44 @@ mod tests {
         assert_eq!(curr.to_string(), "10000");
     }
 
+    #[test]
+    fn test_str_parse_precision() {
+        let curr = Currency::from_str("1.5").unwrap();
+        assert_eq!(curr.winston, 500_000_000_000);
+        assert_eq!(curr.to_string(), "1500000000000");
+
+        let curr = Currency::from_str("1234567123123123123").unwrap();
+        assert_eq!(curr.arweave, 1234567);
+        assert_eq!(curr.winston, 123123123123);
+
+        assert!(matches!(
+            Currency::from_str("999.1231231231239"),
+            Err(Error::InvalidAmount(_))
+        ));
+        assert!(matches!(
+            Currency::from_str("1.-5"),
+            Err(Error::InvalidAmount(_))
+        ));
+    }
+
+    #[test]
+    fn test_str_parse_above_max_supply() {
+        let max = Currency::from_str(&MAX_SUPPLY_WINSTONS.to_string()).unwrap();
+        assert_eq!(max.to_string(), MAX_SUPPLY_WINSTONS.to_string());
+
+        for amount in [
+            (MAX_SUPPLY_WINSTONS + 1).to_string(),
+            "100000000000000000000000000000000".to_owned(),
+            u128::MAX.to_string(),
+            "66000000.000000000001".to_owned(),
+        ] {
+            assert!(matches!(
+                Currency::from_str(&amount),
+                Err(Error::InvalidAmount(a)) if a == amount
+            ));
+        }
+    }
+
     #[test]
     fn test_u64_format() {
         let curr = Currency::from(1_000_000_000_000);
@@ -150,4 +215,18 @@ mod tests {
         assert_eq!(curr.arweave, 0);
         assert_eq!(curr.to_string(), "10000");
     }
+
+    #[test]
+    fn test_to_ar_string() {
+        assert_eq!(Currency::from(0).to_ar_string(), "0.000000000000");
+        assert_eq!(Currency::from(1).to_ar_string(), "0.000000000001");
+        assert_eq!(
+            Currency::from(1_500_000_000_000).to_ar_string(),
+            "1.500000000000"
+        );
+        assert_eq!(
+            Currency::from(123_456_789_000_000_000_001).to_ar_string(),
+            "123456789.000000000001"
+        );
+    }
 }
@@ -0,0 +1,683 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
+
+use futures::future;
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    client::{http_client, normalize_base_url},
+    consts::{ARWEAVE_BASE_URL, DOWNLOAD_PEER_MAX_FAILURES, DOWNLOAD_PEER_TIMEOUT},
+    crypto::{
+        base64::Base64,
+        merkle::{validate_chunk_path, HASH_SIZE},
+    },
+    error::Error,
+    types::{ArnsRecord, ChunkResponse, TxOffset},
+};
+
+#[derive(Clone)]
+pub struct Downloader {
+    url: url::Url,
+    client: Client,
+    peer_timeout: Duration,
+}
+
+impl Default for Downloader {
+    fn default() -> Self {
+        let url = url::Url::from_str(ARWEAVE_BASE_URL).unwrap();
+        Self::new(url)
+    }
+}
+
+impl Downloader {
+    pub fn new(url: url::Url) -> Self {
+        Downloader {
+            url: normalize_base_url(url),
+            client: http_client(),
+            peer_timeout: Duration::from_secs(DOWNLOAD_PEER_TIMEOUT),
+        }
+    }
+
+    /// Sends every request with `client`, so that chunk requests share its connection pool
+    /// with other clients.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Sets how long to wait for a peer to answer a single offset or chunk request before
+    /// trying another peer.
+    pub fn with_peer_timeout(mut self, timeout: Duration) -> Self {
+        self.peer_timeout = timeout;
+        self
+    }
+
+    /// Fetches the transaction data from the gateway `/raw/{id}` endpoint. Returns `None`
+    /// if the gateway doesn't serve the data.
+    pub async fn get_raw(&self, id: &Base64) -> Result<Option<Vec<u8>>, Error> {
+        let url = self.url.join(&format!("raw/{}", id))?;
+        let res = self.client.get(url).send().await?;
+
+        match res.status() {
+            StatusCode::OK => Ok(Some(res.bytes().await?.to_vec())),
+            StatusCode::NOT_FOUND => Ok(None),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    /// Fetches `path` from the gateway root, which serves transaction data by id and
+    /// follows path manifests.
+    pub async fn get_data(&self, path: &str) -> Result<Vec<u8>, Error> {
+        let url = self.url.join(path)?;
+        let res = self.client.get(url).send().await?;
+
+        match res.status() {
+            StatusCode::OK => Ok(res.bytes().await?.to_vec()),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    /// Fetches `path` from the gateway and parses the response as JSON.
+    pub async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
+        let data = self.get_data(path).await?;
+        serde_json::from_slice(&data).map_err(Error::SerdeJsonError)
+    }
+
+    /// Fetches `path` from the gateway as utf-8 text.
+    pub async fn get_text(&self, path: &str) -> Result<String, Error> {
+        let data = self.get_data(path).await?;
+        String::from_utf8(data).map_err(Error::FromUtf8Error)
+    }
+
+    /// Resolves an ArNS name to a transaction id using the gateway resolver endpoint.
+    pub async fn resolve_arns(&self, name: &str) -> Result<Base64, Error> {
+        let url = self.url.join(&format!("ar-io/resolver/records/{}", name))?;
+        let res = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| Error::ArnsResolutionError(e.to_string()))?;
+
+        match res.status() {
+            StatusCode::OK => res
+                .json::<ArnsRecord>()
+                .await
+                .map(|record| record.tx_id)
+                .map_err(|e| Error::ArnsResolutionError(e.to_string())),
+            status => Err(Error::ArnsResolutionError(status.to_string())),
+        }
+    }
+
+    pub async fn get_tx_offset(&self, id: &Base64) -> Result<TxOffset, Error> {
+        self.get_tx_offset_from(&self.url, id).await
+    }
+
+    async fn get_tx_offset_from(&self, node: &url::Url, id: &Base64) -> Result<TxOffset, Error> {
+        let url = node.join(&format!("tx/{}/offset", id))?;
+        let res = self
+            .client
+            .get(url)
+            .timeout(self.peer_timeout)
+            .send()
+            .await?;
+
+        match res.status() {
+            StatusCode::OK => res
+                .json::<TxOffset>()
+                .await
+                .map_err(|err| Error::TransactionInfoError(err.to_string())),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    /// Fetches the data root of the transaction from the gateway. Chunks served by peers are
+    /// checked against it, so it's never taken from the peers themselves.
+    pub async fn get_data_root(&self, id: &Base64) -> Result<[u8; HASH_SIZE], Error> {
+        let url = self.url.join(&format!("tx/{}/data_root", id))?;
+        let res = self.client.get(url).send().await?;
+
+        match res.status() {
+            StatusCode::OK => Base64::from_str(&res.text().await?)?
+                .0
+                .try_into()
+                .map_err(|_| {
+                    Error::TransactionInfoError("transaction has no data root".to_owned())
+                }),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    /// Fetches the size of the transaction data from the gateway. Peers reporting another
+    /// size for the transaction aren't downloaded from.
+    pub async fn get_data_size(&self, id: &Base64) -> Result<u64, Error> {
+        let url = self.url.join(&format!("tx/{}/data_size", id))?;
+        let res = self.client.get(url).send().await?;
+
+        match res.status() {
+            StatusCode::OK => Ok(res.text().await?.parse::<u64>()?),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    /// Fetches the chunk containing the byte at the absolute weave `offset`.
+    pub async fn get_chunk(&self, offset: u64) -> Result<ChunkResponse, Error> {
+        self.get_chunk_from(&self.url, offset).await
+    }
+
+    async fn get_chunk_from(&self, node: &url::Url, offset: u64) -> Result<ChunkResponse, Error> {
+        let url = node.join(&format!("chunk/{}", offset))?;
+        let res = self
+            .client
+            .get(url)
+            .timeout(self.peer_timeout)
+            .send()
+            .await
+            .map_err(|e| Error::GetChunkError(e.to_string()))?;
+
+        match res.status() {
+            StatusCode::OK => res
+                .json::<ChunkResponse>()
+                .await
+                .map_err(|e| Error::GetChunkError(e.to_string())),
+            status => Err(Error::GetChunkError(status.to_string())),
+        }
+    }
+
+    /// Downloads the transaction data chunk by chunk.
+    #[tracing::instrument(skip_all, fields(id = %id))]
+    pub async fn download_tx_data(&self, id: &Base64) -> Result<Vec<u8>, Error> {
+        let mut data = Vec::new();
+        self.download_tx_data_to_writer(id, &mut data).await?;
+        Ok(data)
+    }
+
+    /// Downloads the transaction data chunk by chunk, writing each chunk to `writer` as
+    /// soon as it's fetched. Returns the number of bytes written.
+    pub async fn download_tx_data_to_writer<W: Write>(
+        &self,
+        id: &Base64,
+        writer: &mut W,
+    ) -> Result<u64, Error> {
+        self.download_tx_data_from_peers(id, std::slice::from_ref(&self.url), writer)
+            .await
+    }
+
+    /// Downloads the transaction data chunk by chunk from `peers`, spreading the chunks
+    /// over the peers in turn and trying the remaining peers when one fails.
+    ///
+    /// Only peers that know the offset of the transaction are downloaded from, since the
+    /// others don't hold its data. The size of the data is taken from the gateway, and peers
+    /// reporting another size, or an offset other than the first matching peer, are skipped.
+    /// Every chunk is validated against the data root fetched from the gateway, so peers
+    /// don't need to be trusted.
+    pub async fn download_tx_data_from_peers<W: Write>(
+        &self,
+        id: &Base64,
+        peers: &[url::Url],
+        writer: &mut W,
+    ) -> Result<u64, Error> {
+        let data_root = self.get_data_root(id).await?;
+        let data_size = self.get_data_size(id).await?;
+        let offsets =
+            future::join_all(peers.iter().map(|peer| self.get_tx_offset_from(peer, id))).await;
+
+        let mut last_err = Error::NoneError("peers".to_owned());
+        let mut tx_offset = None;
+        let mut holders = vec![];
+        for (peer, offset) in peers.iter().zip(offsets) {
+            let offset = offset.and_then(|offset| {
+                offset.start()?;
+                if offset.size != data_size || tx_offset.is_some_and(|first| first != offset) {
+                    return Err(Error::TransactionInfoError(format!(
+                        "peer reported {} bytes at offset {}, expected {} bytes",
+                        offset.size, offset.offset, data_size
+                    )));
+                }
+                Ok(offset)
+            });
+            match offset {
+                Ok(offset) => {
+                    tx_offset.get_or_insert(offset);
+                    holders.push(peer.clone());
+                }
+                Err(err) => {
+                    tracing::debug!(%peer, error = %err, "skipping peer");
+                    last_err = err;
+                }
+            }
+        }
+        let tx_offset = tx_offset.ok_or(last_err)?;
+        self.write_chunks(&holders, &tx_offset, data_root, writer)
+            .await
+    }
+
+    /// Downloads the transaction data chunk by chunk into the file at `path`, creating it if
+    /// needed and pre-allocating it to the size of the data.
+    pub async fn download_tx_data_to_file(&self, id: &Base64, path: PathBuf) -> Result<(), Error> {
+        let data_root = self.get_data_root(id).await?;
+        let tx_offset = self.get_tx_offset(id).await?;
+        let file = File::create(path)?;
+        file.set_len(tx_offset.size)?;
+
+        let mut writer = BufWriter::new(file);
+        self.write_chunks(
+            std::slice::from_ref(&self.url),
+            &tx_offset,
+            data_root,
+            &mut writer,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Peers that fail [`DOWNLOAD_PEER_MAX_FAILURES`] chunk requests are dropped for the
+    /// rest of the download, leaving their chunks to the remaining peers.
+    async fn write_chunks<W: Write>(
+        &self,
+        peers: &[url::Url],
+        tx_offset: &TxOffset,
+        data_root: [u8; HASH_SIZE],
+        writer: &mut W,
+    ) -> Result<u64, Error> {
+        let start = tx_offset.start()?;
+        let size = tx_offset.size;
+
+        let mut written = 0;
+        let mut turn = 0;
+        let mut failures = vec![0; peers.len()];
+        while written < size {
+            let mut chunk = Err(Error::NoneError("peers".to_owned()));
+            for idx in (0..peers.len()).cycle().skip(turn).take(peers.len()) {
+                if failures[idx] >= DOWNLOAD_PEER_MAX_FAILURES {
+                    continue;
+                }
+                // A peer may serve bytes that don't belong to the transaction, along with a
+                // proof made up for them, so check the whole proof against the data root and
+                // that the chunk it proves starts where the written data ends.
+                chunk = self
+                    .get_chunk_from(&peers[idx], start + written)
+                    .await
+                    .and_then(|chunk| {
+                        let range =
+                            validate_chunk_path(data_root, &chunk.data_path.0, &chunk.chunk.0)?;
+                        let (chunk_start, chunk_end) = (range.start as u64, range.end as u64);
+                        if chunk_start != written || chunk_end > size {
+                            return Err(Error::ChunkOutOfRange {
+                                start: chunk_start,
+                                end: chunk_end,
+                                offset: written,
+                                size,
+                            });
+                        }
+                        Ok(chunk)
+                    });
+                match chunk {
+                    Ok(_) => break,
+                    Err(_) => failures[idx] += 1,
+                }
+            }
+            let chunk = chunk?;
+            writer.write_all(&chunk.chunk.0)?;
+            written += chunk.chunk.0.len() as u64;
+            turn += 1;
+        }
+        writer.flush()?;
+
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Cursor,
+        time::{Duration, Instant},
+    };
+
+    use httpmock::{Method::GET, Mock, MockServer};
+    use serde_json::json;
+    use tokio_test::block_on;
+
+    use crate::{
+        consts::DOWNLOAD_PEER_MAX_FAILURES,
+        crypto::base64::Base64,
+        error::Error,
+        test_utils::{chunk_tree, mock_data_root, mock_data_size},
+    };
+
+    use super::Downloader;
+
+    /// Serves `chunks` as the chunks of a transaction starting at weave offset 1000.
+    fn mock_chunks<'a>(server: &'a MockServer, chunks: &[&[u8]]) -> Vec<Mock<'a>> {
+        let (_, responses) = chunk_tree(chunks);
+        let mut offset = 1000;
+        chunks
+            .iter()
+            .zip(responses)
+            .map(|(chunk, response)| {
+                let path = format!("/chunk/{}", offset);
+                offset += chunk.len();
+                server.mock(|when, then| {
+                    when.method(GET).path(path);
+                    then.status(200).json_body(response);
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_download_tx_data() {
+        let id = Base64(vec![1; 32]);
+        let chunks: [&[u8]; 2] = [b"hello ", b"world"];
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", id));
+            then.status(200)
+                .json_body(json!({ "size": "11", "offset": "1010" }));
+        });
+        mock_data_root(&server, &id, &chunk_tree(&chunks).0);
+        mock_data_size(&server, &id, 11);
+        mock_chunks(&server, &chunks);
+
+        let url = url::Url::parse(&server.url("/")).unwrap();
+        let downloader = Downloader::new(url);
+        let data = block_on(downloader.download_tx_data(&id)).unwrap();
+
+        assert_eq!(data, b"hello world".to_vec());
+    }
+
+    #[test]
+    fn test_download_tx_data_to_writer() {
+        let id = Base64(vec![1; 32]);
+        let source: Vec<u8> = (0..=255).cycle().take(600).collect();
+        let chunks = [&source[..256], &source[256..512], &source[512..]];
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", id));
+            then.status(200)
+                .json_body(json!({ "size": "600", "offset": "1599" }));
+        });
+        mock_data_root(&server, &id, &chunk_tree(&chunks).0);
+        mock_data_size(&server, &id, 600);
+        mock_chunks(&server, &chunks);
+
+        let url = url::Url::parse(&server.url("/")).unwrap();
+        let downloader = Downloader::new(url);
+        let mut cursor = Cursor::new(Vec::new());
+        let written = block_on(downloader.download_tx_data_to_writer(&id, &mut cursor)).unwrap();
+
+        assert_eq!(written, 600);
+        assert_eq!(cursor.into_inner(), source);
+    }
+
+    #[test]
+    fn test_download_with_shared_client() {
+        let id = Base64(vec![1; 32]);
+        let chunks: [&[u8]; 2] = [b"hello ", b"world"];
+        let (data_root, responses) = chunk_tree(&chunks);
+        let server = MockServer::start();
+        let offset_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/tx/{}/offset", id))
+                .header("x-client", "shared");
+            then.status(200)
+                .json_body(json!({ "size": "11", "offset": "1010" }));
+        });
+        mock_data_root(&server, &id, &data_root);
+        mock_data_size(&server, &id, 11);
+        let chunk_mocks: Vec<_> = [1000, 1006]
+            .into_iter()
+            .zip(responses)
+            .map(|(offset, response)| {
+                server.mock(|when, then| {
+                    when.method(GET)
+                        .path(format!("/chunk/{}", offset))
+                        .header("x-client", "shared");
+                    then.status(200).json_body(response);
+                })
+            })
+            .collect();
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-client", "shared".parse().unwrap());
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()
+            .unwrap();
+        let downloader =
+            Downloader::new(url::Url::parse(&server.url("/")).unwrap()).with_client(client);
+        let data = block_on(downloader.download_tx_data(&id)).unwrap();
+
+        assert_eq!(data, b"hello world".to_vec());
+        offset_mock.assert();
+        chunk_mocks.iter().for_each(|mock| mock.assert());
+    }
+
+    #[test]
+    fn test_reject_chunk_not_matching_data_root() {
+        let id = Base64(vec![1; 32]);
+        let chunks: [&[u8]; 1] = [b"hello world"];
+        let gateway = MockServer::start();
+        let forger = MockServer::start();
+        for server in [&gateway, &forger] {
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/tx/{}/offset", id));
+                then.status(200)
+                    .json_body(json!({ "size": "11", "offset": "1010" }));
+            });
+        }
+        mock_data_root(&gateway, &id, &chunk_tree(&chunks).0);
+        mock_data_size(&gateway, &id, 11);
+        let honest_mocks = mock_chunks(&gateway, &chunks);
+        // Same size as the real chunk, served with a valid proof against a root of its own.
+        let forged_mocks = mock_chunks(&forger, &[b"HELLO WORLD"]);
+
+        let gateway_url = url::Url::parse(&gateway.url("/")).unwrap();
+        let forger_url = url::Url::parse(&forger.url("/")).unwrap();
+        let downloader = Downloader::new(gateway_url.clone());
+        let mut data = Vec::new();
+        assert!(matches!(
+            block_on(downloader.download_tx_data_from_peers(
+                &id,
+                std::slice::from_ref(&forger_url),
+                &mut data
+            )),
+            Err(Error::InvalidProof)
+        ));
+        assert!(data.is_empty());
+
+        let peers = [forger_url, gateway_url];
+        block_on(downloader.download_tx_data_from_peers(&id, &peers, &mut data)).unwrap();
+
+        assert_eq!(data, b"hello world".to_vec());
+        forged_mocks[0].assert_hits(2);
+        honest_mocks[0].assert();
+    }
+
+    #[test]
+    fn test_reject_chunk_out_of_range() {
+        let id = Base64(vec![1; 32]);
+        let chunks: [&[u8]; 2] = [b"hello ", b"world"];
+        let server = MockServer::start();
+        // Claims fewer bytes than the tree proven by the data root covers.
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", id));
+            then.status(200)
+                .json_body(json!({ "size": "8", "offset": "1007" }));
+        });
+        mock_data_root(&server, &id, &chunk_tree(&chunks).0);
+        mock_data_size(&server, &id, 8);
+        mock_chunks(&server, &chunks);
+
+        let downloader = Downloader::new(url::Url::parse(&server.url("/")).unwrap());
+        let mut data = Vec::new();
+        let res = block_on(downloader.download_tx_data_to_writer(&id, &mut data));
+
+        assert!(matches!(
+            res,
+            Err(Error::ChunkOutOfRange {
+                start: 6,
+                end: 11,
+                offset: 6,
+                size: 8,
+            })
+        ));
+        assert_eq!(data, b"hello ".to_vec());
+    }
+
+    #[test]
+    fn test_reject_misaligned_chunk() {
+        let id = Base64(vec![1; 32]);
+        let chunks: [&[u8]; 2] = [b"hello ", b"world"];
+        let (data_root, responses) = chunk_tree(&chunks);
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", id));
+            then.status(200)
+                .json_body(json!({ "size": "11", "offset": "1010" }));
+        });
+        mock_data_root(&server, &id, &data_root);
+        mock_data_size(&server, &id, 11);
+        // Valid chunk and proof, but for the second chunk rather than the first.
+        server.mock(|when, then| {
+            when.method(GET).path("/chunk/1000");
+            then.status(200).json_body(responses[1].clone());
+        });
+
+        let downloader = Downloader::new(url::Url::parse(&server.url("/")).unwrap());
+        let res = block_on(downloader.download_tx_data(&id));
+
+        assert!(matches!(
+            res,
+            Err(Error::ChunkOutOfRange {
+                start: 6,
+                end: 11,
+                offset: 0,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_download_only_from_peers_holding_tx() {
+        let id = Base64(vec![1; 32]);
+        let chunks: [&[u8]; 2] = [b"hello ", b"world"];
+        let missing = MockServer::start();
+        let holder = MockServer::start();
+        let missing_offset = missing.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", id));
+            then.status(404);
+        });
+        holder.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", id));
+            then.status(200)
+                .json_body(json!({ "size": "11", "offset": "1010" }));
+        });
+        mock_data_root(&holder, &id, &chunk_tree(&chunks).0);
+        mock_data_size(&holder, &id, 11);
+        let missing_chunks = mock_chunks(&missing, &chunks);
+        let holder_chunks = mock_chunks(&holder, &chunks);
+
+        let peers = [&missing, &holder].map(|server| url::Url::parse(&server.url("/")).unwrap());
+        let downloader = Downloader::new(peers[1].clone());
+        let mut data = Vec::new();
+        block_on(downloader.download_tx_data_from_peers(&id, &peers, &mut data)).unwrap();
+
+        assert_eq!(data, b"hello world".to_vec());
+        missing_offset.assert();
+        missing_chunks.iter().for_each(|mock| mock.assert_hits(0));
+        holder_chunks.iter().for_each(|mock| mock.assert());
+    }
+
+    #[test]
+    fn test_download_skips_peers_with_wrong_offset() {
+        let id = Base64(vec![1; 32]);
+        let chunks: [&[u8]; 2] = [b"hello ", b"world"];
+        let holder = MockServer::start();
+        let truncating = MockServer::start();
+        let overflowing = MockServer::start();
+        // Ends the data at the chunk boundary, which would still pass chunk validation.
+        truncating.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", id));
+            then.status(200)
+                .json_body(json!({ "size": "6", "offset": "1005" }));
+        });
+        overflowing.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", id));
+            then.status(200)
+                .json_body(json!({ "size": "2000", "offset": "1000" }));
+        });
+        holder.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", id));
+            then.status(200)
+                .json_body(json!({ "size": "11", "offset": "1010" }));
+        });
+        mock_data_root(&holder, &id, &chunk_tree(&chunks).0);
+        mock_data_size(&holder, &id, 11);
+        let truncating_chunks = mock_chunks(&truncating, &chunks);
+        let holder_chunks = mock_chunks(&holder, &chunks);
+
+        let peers = [&truncating, &overflowing, &holder]
+            .map(|server| url::Url::parse(&server.url("/")).unwrap());
+        let downloader = Downloader::new(peers[2].clone());
+        let mut data = Vec::new();
+        assert!(matches!(
+            block_on(downloader.download_tx_data_from_peers(&id, &peers[..2], &mut data)),
+            Err(Error::TransactionInfoError(_) | Error::OffsetOutOfRange(..))
+        ));
+
+        block_on(downloader.download_tx_data_from_peers(&id, &peers, &mut data)).unwrap();
+
+        assert_eq!(data, b"hello world".to_vec());
+        truncating_chunks
+            .iter()
+            .for_each(|mock| mock.assert_hits(0));
+        holder_chunks.iter().for_each(|mock| mock.assert());
+    }
+
+    #[test]
+    fn test_download_evicts_slow_peer() {
+        let id = Base64(vec![1; 32]);
+        let data = b"abcdefghijklmnop";
+        let chunks: Vec<&[u8]> = data.chunks(2).collect();
+        let slow = MockServer::start();
+        let fast = MockServer::start();
+        for server in [&slow, &fast] {
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/tx/{}/offset", id));
+                then.status(200)
+                    .json_body(json!({ "size": "16", "offset": "1015" }));
+            });
+        }
+        mock_data_root(&fast, &id, &chunk_tree(&chunks).0);
+        mock_data_size(&fast, &id, 16);
+        let slow_chunks: Vec<_> = (0..chunks.len())
+            .map(|i| {
+                slow.mock(|when, then| {
+                    when.method(GET).path(format!("/chunk/{}", 1000 + 2 * i));
+                    then.status(200).delay(Duration::from_secs(5));
+                })
+            })
+            .collect();
+        mock_chunks(&fast, &chunks);
+
+        let peers = [&slow, &fast].map(|server| url::Url::parse(&server.url("/")).unwrap());
+        let downloader =
+            Downloader::new(peers[1].clone()).with_peer_timeout(Duration::from_millis(200));
+        let started = Instant::now();
+        let mut downloaded = Vec::new();
+        block_on(downloader.download_tx_data_from_peers(&id, &peers, &mut downloaded)).unwrap();
+
+        assert_eq!(downloaded, data);
+        assert!(started.elapsed() < Duration::from_secs(3));
+        let slow_hits: usize = slow_chunks.iter().map(|mock| mock.hits()).sum();
+        assert_eq!(slow_hits, DOWNLOAD_PEER_MAX_FAILURES);
+    }
+}
@@ -3,6 +3,8 @@ use std::string::FromUtf8Error;
 use thiserror::Error;
 use url::ParseError;
 
+use crate::types::{ConfirmationState, VerificationCheck};
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Error getting oracle price: {0}")]
@@ -11,15 +13,51 @@ pub enum Error {
     #[error("Getting Arweave price from oracle: {0}")]
     GetPriceError(String),
 
+    #[error("Gateway returned server error {0}")]
+    ServerError(u16),
+
+    #[error("Upload cancelled")]
+    Cancelled,
+
+    #[error("Upload task failed: {0}")]
+    UploadTaskFailed(String),
+
     #[error("Status code not Ok")]
     StatusCodeNotOk,
 
+    #[error("No signer configured")]
+    NoSigner,
+
     #[error("Unsigned transaction")]
     UnsignedTransaction,
 
+    #[error("Transaction is already signed")]
+    AlreadySigned,
+
+    #[error("Transaction rejected with status {0}: {1}")]
+    TransactionRejected(u16, String),
+
+    #[error("Invalid anchor: expected 32 or 48 bytes, got {0}")]
+    InvalidAnchor(usize),
+
+    #[error("Transaction data of {size} bytes exceeds the gateway limit of {limit} bytes, upload it in chunks instead")]
+    DataTooLarge { size: u64, limit: u64 },
+
+    #[error("Transaction anchor is stale")]
+    StaleAnchor,
+
+    #[error("Timed out waiting for confirmation, last state: {0:?}")]
+    ConfirmationTimeout(ConfirmationState),
+
     #[error("Invalid proof")]
     InvalidProof,
 
+    #[error("Offset {0} out of range for {1} bytes of data")]
+    OffsetOutOfRange(usize, usize),
+
+    #[error("Invalid AR amount: {0}")]
+    InvalidAmount(String),
+
     #[error("Slice error")]
     SliceError,
 
@@ -50,9 +88,37 @@ pub enum Error {
     #[error("Invalid signature")]
     InvalidSignature,
 
+    #[error("Transaction failed {0:?} verification")]
+    TransactionVerificationFailed(VerificationCheck),
+
+    #[error("Upload aborted after using up its budget of {0} chunk retries")]
+    RetryBudgetExhausted(usize),
+
     #[error("Error posting chunk: {0}")]
     PostChunkError(String),
 
+    #[error("Error getting chunk: {0}")]
+    GetChunkError(String),
+    #[error("Chunk covers bytes {start}..{end}, expected a chunk from offset {offset} of {size} bytes of data")]
+    ChunkOutOfRange {
+        start: u64,
+        end: u64,
+        offset: u64,
+        size: u64,
+    },
+
+    #[error("Invalid ar:// uri: {0}")]
+    InvalidArUri(String),
+
+    #[error("Error resolving ArNS name: {0}")]
+    ArnsResolutionError(String),
+
+    #[error("Invalid split upload: {0}")]
+    SplitUploadError(String),
+
+    #[error("Manifest error: {0}")]
+    ManifestError(String),
+
     #[error("Error signing: {0}")]
     SigningError(String),
 
@@ -89,3 +155,92 @@ impl From<std::io::Error> for Error {
         Self::IoError(value)
     }
 }
+
+impl From<std::num::ParseIntError> for Error {
+    fn from(value: std::num::ParseIntError) -> Self {
+        Self::ParseIntError(value)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(value: ParseError) -> Self {
+        Self::UrlParseError(value)
+    }
+}
+
+impl Error {
+    /// Whether the error means the gateway couldn't serve the request, as opposed to the
+    /// request itself being wrong, so that another gateway may succeed.
+    pub fn is_gateway_unavailable(&self) -> bool {
+        match self {
+            Error::ServerError(_) => true,
+            Error::ReqwestError(err) => err.is_connect() || err.is_timeout(),
+            _ => false,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(value: reqwest::Error) -> Self {
+        Self::ReqwestError(value)
+    }
+}
+
+impl From<base64::DecodeError> for Error {
+    fn from(value: base64::DecodeError) -> Self {
+        Self::Base64DecodeError(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::Error;
+    use crate::crypto::base64::Base64;
+
+    fn parse_int(s: &str) -> Result<u64, Error> {
+        Ok(s.parse::<u64>()?)
+    }
+
+    fn parse_url(s: &str) -> Result<url::Url, Error> {
+        Ok(url::Url::parse(s)?)
+    }
+
+    fn parse_base64(s: &str) -> Result<Base64, Error> {
+        Ok(Base64::from_str(s)?)
+    }
+
+    fn build_request(s: &str) -> Result<reqwest::Request, Error> {
+        Ok(reqwest::Client::new().get(s).build()?)
+    }
+
+    #[test]
+    fn test_from_parse_int_error() {
+        assert!(matches!(parse_int("nan"), Err(Error::ParseIntError(_))));
+    }
+
+    #[test]
+    fn test_from_url_parse_error() {
+        assert!(matches!(
+            parse_url("not a url"),
+            Err(Error::UrlParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_base64_decode_error() {
+        assert!(matches!(
+            parse_base64("not base64!"),
+            Err(Error::Base64DecodeError(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_reqwest_error() {
+        assert!(matches!(
+            build_request("not a url"),
+            Err(Error::ReqwestError(_))
+        ));
+    }
+}
@@ -1,35 +1,57 @@
-use std::{fs, path::PathBuf, str::FromStr};
+use std::{fs, io::Write, path::PathBuf, str::FromStr, sync::Arc, thread, time::Duration};
 
-use consts::MAX_TX_DATA;
-use crypto::base64::Base64;
+use client::{http_client, http_client_builder, normalize_base_url};
+use consts::{
+    CONFIRMATION_POLL_MAX_SLEEP, CONFIRMATION_POLL_SLEEP, CONFIRMATION_THRESHOLD, DATA_ROOT_TAG,
+    MAX_TX_DATA, SPLIT_UPLOAD_APP_NAME,
+};
+use crypto::{
+    base64::Base64,
+    hash::{deep_hash, DeepHashItem},
+    merkle::{generate_data_root, generate_leaves},
+};
+use currency::WINSTONS_PER_AR;
+use download::Downloader;
 use error::Error;
-use futures::{stream, Stream, StreamExt};
+use futures::{future, stream, Future, Stream, StreamExt, TryStreamExt};
+use futures_timer::Delay;
+use network::NetworkInfoClient;
 use pretend::StatusCode;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use reqwest::{redirect, Client};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
 use transaction::{
     client::TxClient,
     tags::{FromUtf8Strs, Tag},
     Tx,
 };
-use types::TxStatus;
-use upload::Uploader;
+use types::{ConfirmationState, NetworkInfo, TxField, TxStatus};
+use upload::{UploadHandle, UploadProgress, Uploader};
 use verify::{verify, verify_transaction};
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod client;
 pub mod consts;
 pub mod crypto;
 pub mod currency;
+pub mod download;
 pub mod error;
+pub mod manifest;
 pub mod network;
 pub mod signer;
+#[cfg(test)]
+mod test_utils;
 pub mod transaction;
 pub mod types;
 pub mod upload;
+pub mod verifier;
 mod verify;
 pub mod wallet;
 
-pub use signer::ArweaveSigner;
+pub use signer::{ArweaveSigner, WalletStore};
+pub use verifier::Verifier;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct OraclePrice {
@@ -41,17 +63,64 @@ pub struct OraclePricePair {
     pub usd: f32,
 }
 
+/// Cost of uploading a given number of bytes.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UploadEstimate {
+    pub winston_fee: u64,
+    pub ar: f64,
+    pub usd: Option<f64>,
+}
+
+/// How [`Arweave::wait_for_confirmation`] polls the transaction status. The interval
+/// between polls starts at `initial_interval` and doubles up to `max_interval`.
+#[derive(Debug, Clone)]
+pub struct ConfirmationPolling {
+    pub initial_interval: Duration,
+    pub max_interval: Duration,
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ConfirmationPolling {
+    fn default() -> Self {
+        Self {
+            initial_interval: Duration::from_secs(CONFIRMATION_POLL_SLEEP),
+            max_interval: Duration::from_secs(CONFIRMATION_POLL_MAX_SLEEP),
+            max_attempts: None,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Arweave {
     pub base_url: url::Url,
     pub signer: Option<ArweaveSigner>,
+    oracle_url: url::Url,
+    sort_tags: bool,
+    verify_data_root: bool,
+    check_anchor: bool,
+    confirmation_polling: ConfirmationPolling,
     tx_client: TxClient,
+    client: Client,
+    fallback_clients: Vec<TxClient>,
     uploader: Uploader,
+    downloader: Downloader,
 }
 
 #[derive(Default)]
 pub struct ArweaveBuilder {
     base_url: Option<url::Url>,
     keypair_path: Option<PathBuf>,
+    oracle_url: Option<url::Url>,
+    max_in_flight_bytes: Option<usize>,
+    sort_tags: bool,
+    verify_data_root: bool,
+    check_anchor: bool,
+    confirmation_polling: Option<ConfirmationPolling>,
+    fallback_gateways: Vec<url::Url>,
+    download_peer_timeout: Option<Duration>,
+    client: Option<Client>,
+    pool_max_idle_per_host: Option<usize>,
+    max_redirects: Option<usize>,
 }
 
 impl ArweaveBuilder {
@@ -69,35 +138,163 @@ impl ArweaveBuilder {
         self
     }
 
+    pub fn oracle_url(mut self, url: url::Url) -> ArweaveBuilder {
+        self.oracle_url = Some(url);
+        self
+    }
+
+    pub fn max_in_flight_bytes(mut self, max_bytes: usize) -> ArweaveBuilder {
+        self.max_in_flight_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Re-chunks the data of new transactions and validates the data root and every proof
+    /// against it, see [`Tx::validate_data_root`]. Off by default as it doubles the work of
+    /// chunking large uploads.
+    pub fn verify_data_root(mut self, verify_data_root: bool) -> ArweaveBuilder {
+        self.verify_data_root = verify_data_root;
+        self
+    }
+
+    /// Checks the anchor of transactions before posting them and fails with
+    /// [`Error::StaleAnchor`] if it is older than [`consts::ANCHOR_MAX_AGE_BLOCKS`] blocks,
+    /// instead of leaving it to the gateway to reject them.
+    pub fn check_anchor(mut self, check_anchor: bool) -> ArweaveBuilder {
+        self.check_anchor = check_anchor;
+        self
+    }
+
+    /// Sorts transaction tags before signing, see [`Tx::sort_tags`].
+    pub fn sort_tags(mut self, sort_tags: bool) -> ArweaveBuilder {
+        self.sort_tags = sort_tags;
+        self
+    }
+
+    pub fn confirmation_polling(mut self, polling: ConfirmationPolling) -> ArweaveBuilder {
+        self.confirmation_polling = Some(polling);
+        self
+    }
+
+    /// Gateways that read operations fall back to, in order, when the base gateway is
+    /// unreachable or returns a server error.
+    pub fn fallback_gateways(mut self, gateways: Vec<url::Url>) -> ArweaveBuilder {
+        self.fallback_gateways = gateways;
+        self
+    }
+
+    /// How long to wait for a peer to answer a single request while downloading, see
+    /// [`Downloader::with_peer_timeout`].
+    pub fn download_peer_timeout(mut self, timeout: Duration) -> ArweaveBuilder {
+        self.download_peer_timeout = Some(timeout);
+        self
+    }
+
+    /// HTTP client shared by all requests to the gateways, a new one is created if not set.
+    pub fn client(mut self, client: Client) -> ArweaveBuilder {
+        self.client = Some(client);
+        self
+    }
+
+    /// Maximum number of idle connections kept open to each host by the client created when
+    /// none is set with [`ArweaveBuilder::client`].
+    pub fn pool_max_idle_per_host(mut self, max_idle: usize) -> ArweaveBuilder {
+        self.pool_max_idle_per_host = Some(max_idle);
+        self
+    }
+
+    /// Maximum number of redirects followed by the client created when none is set with
+    /// [`ArweaveBuilder::client`], [`consts::MAX_REDIRECTS`] by default. Gateways redirect
+    /// e.g. manifest paths to the data they point to. 0 disables following redirects, the
+    /// redirect response is then handled like any other unexpected status.
+    pub fn max_redirects(mut self, max_redirects: usize) -> ArweaveBuilder {
+        self.max_redirects = Some(max_redirects);
+        self
+    }
+
     pub fn build(self) -> Result<Arweave, Error> {
-        let base_url = self
-            .base_url
-            .unwrap_or_else(|| url::Url::from_str(consts::ARWEAVE_BASE_URL).unwrap()); //Checked unwrap
+        let base_url = normalize_base_url(
+            self.base_url
+                .unwrap_or_else(|| url::Url::from_str(consts::ARWEAVE_BASE_URL).unwrap()), //Checked unwrap
+        );
+        let oracle_url = self
+            .oracle_url
+            .unwrap_or_else(|| url::Url::from_str(consts::ORACLE_URL).unwrap()); //Checked unwrap
 
         let signer = match self.keypair_path {
             Some(p) => Some(ArweaveSigner::from_keypair_path(p)?),
             None => None,
         };
 
+        let client = match self.client {
+            Some(client) => client,
+            None => {
+                let mut builder = http_client_builder();
+                if let Some(max_idle) = self.pool_max_idle_per_host {
+                    builder = builder.pool_max_idle_per_host(max_idle);
+                }
+                if let Some(max_redirects) = self.max_redirects {
+                    builder = builder.redirect(match max_redirects {
+                        0 => redirect::Policy::none(),
+                        max => redirect::Policy::limited(max),
+                    });
+                }
+                builder.build()?
+            }
+        };
+        let mut uploader = Uploader::new(base_url.clone()).with_client(client.clone());
+        if let Some(max_bytes) = self.max_in_flight_bytes {
+            uploader = uploader.with_max_in_flight_bytes(max_bytes);
+        }
+
+        let mut downloader = Downloader::new(base_url.clone()).with_client(client.clone());
+        if let Some(timeout) = self.download_peer_timeout {
+            downloader = downloader.with_peer_timeout(timeout);
+        }
+
+        let fallback_clients = self
+            .fallback_gateways
+            .into_iter()
+            .map(|gateway| TxClient::new(client.clone(), gateway))
+            .collect::<Result<Vec<_>, _>>()?;
+
         Ok(Arweave {
             signer,
+            tx_client: TxClient::new(client.clone(), base_url.clone())?,
+            client,
+            fallback_clients,
+            uploader,
+            downloader,
             base_url,
-            tx_client: Default::default(),
-            uploader: Default::default(),
+            oracle_url,
+            sort_tags: self.sort_tags,
+            verify_data_root: self.verify_data_root,
+            check_anchor: self.check_anchor,
+            confirmation_polling: self.confirmation_polling.unwrap_or_default(),
         })
     }
 }
 
 impl Arweave {
     pub fn from_keypair_path(keypair_path: PathBuf, base_url: url::Url) -> Result<Arweave, Error> {
+        let base_url = normalize_base_url(base_url);
         let signer = Some(ArweaveSigner::from_keypair_path(keypair_path)?);
-        let tx_client = TxClient::new(reqwest::Client::new(), base_url.clone())?;
-        let uploader = Uploader::new(base_url.clone());
+        let client = http_client();
+        let tx_client = TxClient::new(client.clone(), base_url.clone())?;
+        let uploader = Uploader::new(base_url.clone()).with_client(client.clone());
+        let downloader = Downloader::new(base_url.clone()).with_client(client.clone());
         let arweave = Arweave {
             base_url,
             signer,
+            oracle_url: url::Url::from_str(consts::ORACLE_URL).unwrap(), //Checked unwrap
+            sort_tags: false,
+            verify_data_root: false,
+            check_anchor: false,
+            confirmation_polling: ConfirmationPolling::default(),
             tx_client,
+            client,
+            fallback_clients: vec![],
             uploader,
+            downloader,
         };
         Ok(arweave)
     }
@@ -112,141 +309,930 @@ impl Arweave {
         auto_content_tag: bool,
     ) -> Result<Tx, Error> {
         let last_tx = self.get_last_tx().await?;
-        let signer = match &self.signer {
-            Some(s) => s,
-            None => return Err(Error::NoneError("signer".to_owned())),
-        };
-        Tx::new(
-            signer.get_provider(),
+        self.create_transaction_with_anchor(
             target,
+            other_tags,
             data,
             quantity,
             fee,
+            auto_content_tag,
             last_tx,
+        )
+    }
+
+    /// Creates a transaction with an explicit `anchor` instead of fetching one from the
+    /// network, e.g. for transactions prepared offline.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_transaction_with_anchor(
+        &self,
+        target: Base64,
+        other_tags: Vec<Tag<Base64>>,
+        data: Vec<u8>,
+        quantity: u128,
+        fee: u64,
+        auto_content_tag: bool,
+        anchor: Base64,
+    ) -> Result<Tx, Error> {
+        let signer = self.signer()?;
+        let transaction = Tx::new(
+            signer.get_provider(),
+            target,
+            data,
+            quantity,
+            fee,
+            anchor,
             other_tags,
             auto_content_tag,
-        )
+        )?;
+        if self.verify_data_root {
+            transaction.validate_data_root()?;
+        }
+        Ok(transaction)
     }
 
-    pub fn sign_transaction(&self, transaction: Tx) -> Result<Tx, Error> {
-        let signer = match &self.signer {
-            Some(s) => s,
-            None => return Err(Error::NoneError("signer".to_owned())),
-        };
+    fn signer(&self) -> Result<&ArweaveSigner, Error> {
+        self.signer.as_ref().ok_or(Error::NoSigner)
+    }
+
+    pub fn sign_transaction(&self, mut transaction: Tx) -> Result<Tx, Error> {
+        let signer = self.signer()?;
+        if self.sort_tags {
+            transaction.sort_tags();
+        }
         signer.sign_transaction(transaction)
     }
 
     pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Error> {
-        let signer = match &self.signer {
-            Some(s) => s,
-            None => return Err(Error::NoneError("signer".to_owned())),
-        };
+        let signer = self.signer()?;
         Ok(signer.sign(message)?.0)
     }
 
+    /// Signs `message` for use within `context`, e.g. a login to a given app. The signature
+    /// only verifies with [`Arweave::verify_message_with_context`] for the same context, so
+    /// it can't be replayed elsewhere.
+    pub fn sign_message_with_context(
+        &self,
+        context: &str,
+        message: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        self.sign(&Self::context_message_hash(context, message))
+    }
+
+    pub fn verify_message_with_context(
+        pub_key: &[u8],
+        context: &str,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        verify(
+            pub_key,
+            &Self::context_message_hash(context, message),
+            signature,
+        )
+    }
+
+    fn context_message_hash(context: &str, message: &[u8]) -> [u8; 48] {
+        deep_hash(DeepHashItem::List(vec![
+            DeepHashItem::from_item(consts::MESSAGE_SIGNATURE_PREFIX.as_bytes()),
+            DeepHashItem::from_item(context.as_bytes()),
+            DeepHashItem::from_item(message),
+        ]))
+    }
+
     pub fn verify_transaction(transaction: &Tx) -> Result<(), Error> {
         verify_transaction(transaction)
     }
 
+    /// Same as [`Arweave::verify_transaction`], but also checks that the id matches the
+    /// signature.
+    pub fn verify_transaction_with_id(transaction: &Tx) -> Result<(), Error> {
+        verify::verify_transaction_with_id(transaction)
+    }
+
+    /// Verifies the signature, id and data root of a transaction in one call, see
+    /// [`VerificationCheck`](types::VerificationCheck) for the reported failures.
+    pub fn verify_transaction_full(transaction: &Tx) -> Result<(), Error> {
+        verify::verify_transaction_full(transaction)
+    }
+
+    /// Parses a transaction serialized by another implementation and fully verifies it.
+    pub fn verify_external_tx(json: &str) -> Result<Tx, Error> {
+        verify::verify_external_tx(json)
+    }
+
+    /// Verifies a transaction as returned by `/tx/{id}` straight from its JSON, without
+    /// converting it to a [`Tx`].
+    pub fn verify_json_tx(json: &str) -> Result<(), Error> {
+        verify::verify_json_tx(json)
+    }
+
+    /// Verifies the signatures of `transactions` on up to `concurrency` threads, returning
+    /// one result per transaction in input order.
+    pub fn verify_transactions(transactions: &[Tx], concurrency: usize) -> Vec<Result<(), Error>> {
+        if transactions.is_empty() {
+            return vec![];
+        }
+        let chunk_size = transactions.len().div_ceil(concurrency.max(1));
+        thread::scope(|scope| {
+            let handles: Vec<_> = transactions
+                .chunks(chunk_size)
+                .map(|txs| {
+                    scope.spawn(move || txs.iter().map(verify_transaction).collect::<Vec<_>>())
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|err| std::panic::resume_unwind(err))
+                })
+                .collect()
+        })
+    }
+
     pub fn verify(pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), Error> {
         verify(pub_key, message, signature)
     }
 
+    /// Computes the data root of a file without building a transaction.
+    pub fn data_root_of_file(file_path: PathBuf) -> Result<Base64, Error> {
+        let data = fs::read(file_path)?;
+        Self::data_root_of_bytes(&data)
+    }
+
+    /// Computes the data root of `data` without building a transaction.
+    pub fn data_root_of_bytes(data: &[u8]) -> Result<Base64, Error> {
+        if data.is_empty() {
+            return Ok(Base64::empty());
+        }
+        let leaves = generate_leaves(data.to_vec())?;
+        let root = generate_data_root(leaves)?;
+        Ok(Base64(root.id.to_vec()))
+    }
+
     pub async fn post_transaction(&self, signed_transaction: &Tx) -> Result<(String, u64), Error> {
+        if self.check_anchor {
+            self.check_anchor(&signed_transaction.last_tx).await?;
+        }
         self.tx_client
             .post_transaction(signed_transaction)
             .await
             .map(|(id, reward)| (id.to_string(), reward))
     }
 
+    /// Re-posts an already signed transaction, e.g. after it was dropped from the mempool.
+    /// Transactions with more than [`MAX_TX_DATA`] bytes of data are re-posted as a header
+    /// followed by their chunks, like a new upload.
+    pub async fn rebroadcast(&self, signed_transaction: &Tx) -> Result<(String, u64), Error> {
+        if signed_transaction.id.is_empty() {
+            return Err(Error::UnsignedTransaction);
+        }
+        verify_transaction(signed_transaction)?;
+        self.post_signed_transaction(signed_transaction.clone(), None, None)
+            .await
+    }
+
     async fn get_last_tx(&self) -> Result<Base64, Error> {
         self.tx_client.get_last_tx().await
     }
 
+    /// Fails with [`Error::StaleAnchor`] if `anchor` is a block more than
+    /// [`consts::ANCHOR_MAX_AGE_BLOCKS`] blocks old. Anchors that aren't blocks, i.e. the last
+    /// transaction of the wallet, are left to the gateway to check.
+    async fn check_anchor(&self, anchor: &Base64) -> Result<(), Error> {
+        if *anchor == self.get_last_tx().await? {
+            return Ok(());
+        }
+
+        let network = self.network_client(self.base_url.clone());
+        let block = match network.block_by_hash(&anchor.to_string()).await {
+            Ok(block) => block,
+            Err(_) => return Ok(()),
+        };
+        let height = self.network_info().await?.height;
+        if height.saturating_sub(block.height) > consts::ANCHOR_MAX_AGE_BLOCKS {
+            tracing::warn!(%anchor, anchor_height = block.height, height, "stale anchor");
+            return Err(Error::StaleAnchor);
+        }
+        Ok(())
+    }
+
     pub async fn get_fee(&self, target: Base64, data: Vec<u8>) -> Result<u64, Error> {
-        self.tx_client.get_fee(target, data).await
+        self.get_fee_by_size(target, data.len() as u64).await
+    }
+
+    async fn get_fee_by_size(&self, target: Base64, byte_size: u64) -> Result<u64, Error> {
+        self.read_with_failover(|client| client.get_fee_by_size(target.clone(), byte_size))
+            .await
+    }
+
+    /// Fetches the current AR price in USD from the oracle.
+    pub async fn get_oracle_price(&self) -> Result<f32, Error> {
+        let price = self
+            .client
+            .get(self.oracle_url.clone())
+            .send()
+            .await
+            .map_err(|e| Error::OracleGetPriceError(e.to_string()))?
+            .json::<OraclePrice>()
+            .await
+            .map_err(|e| Error::OracleGetPriceError(e.to_string()))?;
+        Ok(price.arweave.usd)
+    }
+
+    /// Estimates the cost of uploading `byte_size` bytes. `usd` is `None` if the oracle
+    /// price is unavailable.
+    pub async fn estimate_upload(&self, byte_size: u64) -> Result<UploadEstimate, Error> {
+        let winston_fee = self.get_fee_by_size(Base64::empty(), byte_size).await?;
+        let ar = winston_fee as f64 / WINSTONS_PER_AR as f64;
+        let usd = self
+            .get_oracle_price()
+            .await
+            .ok()
+            .map(|price| ar * price as f64);
+
+        Ok(UploadEstimate {
+            winston_fee,
+            ar,
+            usd,
+        })
+    }
+
+    pub async fn network_info(&self) -> Result<NetworkInfo, Error> {
+        self.network_client(self.base_url.clone())
+            .network_info()
+            .await
+            .map_err(|err| Error::NetworkInfoError(err.to_string()))
+    }
+
+    fn network_client(&self, url: url::Url) -> NetworkInfoClient {
+        NetworkInfoClient::with_client(url, self.client.clone())
     }
 
     pub async fn get_tx(&self, id: Base64) -> Result<(StatusCode, Option<Tx>), Error> {
-        self.tx_client.get_tx(id).await
+        self.read_with_failover(|client| client.get_tx(id.clone()))
+            .await
     }
 
     pub async fn get_tx_status(&self, id: Base64) -> Result<(StatusCode, Option<TxStatus>), Error> {
-        self.tx_client.get_tx_status(id).await
+        self.read_with_failover(|client| client.get_tx_status(id.clone()))
+            .await
     }
 
-    pub fn get_pub_key(&self) -> Result<String, Error> {
-        let signer = match &self.signer {
-            Some(s) => s,
-            None => return Err(Error::NoneError("signer".to_owned())),
+    /// Returns a single field of the transaction, e.g. `owner` or `data_root`, without
+    /// fetching the whole transaction.
+    pub async fn get_tx_field(&self, id: Base64, field: &str) -> Result<String, Error> {
+        self.read_with_failover(|client| client.get_tx_field(id.clone(), field))
+            .await
+    }
+
+    /// Same as [`Arweave::get_tx_field`], with the field picked from the ones gateways serve.
+    pub async fn get_known_tx_field(&self, id: Base64, field: TxField) -> Result<String, Error> {
+        self.get_tx_field(id, field.as_str()).await
+    }
+
+    /// Checks that `data` is the data of transaction `id` by recomputing its data root and
+    /// comparing it with the one of the transaction, returning [`Error::InvalidProof`] if they
+    /// differ. Format 1 transactions have no data root, so only empty data verifies for them.
+    pub async fn verify_downloaded_data(&self, id: Base64, data: &[u8]) -> Result<(), Error> {
+        let data_root = Base64::from_str(&self.get_known_tx_field(id, TxField::DataRoot).await?)?;
+        if Self::data_root_of_bytes(data)? != data_root {
+            return Err(Error::InvalidProof);
+        }
+        Ok(())
+    }
+
+    /// Returns the balance of the wallet at `address` in winstons.
+    pub async fn get_balance(&self, address: &str) -> Result<String, Error> {
+        self.read_with_failover(|client| client.get_balance(address))
+            .await
+    }
+
+    /// Returns the number of transactions waiting in the mempool.
+    pub async fn mempool_size(&self) -> Result<usize, Error> {
+        self.read_with_failover(|client| client.get_pending())
+            .await
+            .map(|pending| pending.len())
+    }
+
+    /// Runs `read` against the base gateway, then against each fallback gateway in turn for
+    /// as long as the gateways are unavailable.
+    async fn read_with_failover<'a, T, F, Fut>(&'a self, read: F) -> Result<T, Error>
+    where
+        F: Fn(&'a TxClient) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let mut res = read(&self.tx_client).await;
+        for client in &self.fallback_clients {
+            match &res {
+                Err(err) if err.is_gateway_unavailable() => res = read(client).await,
+                _ => break,
+            }
+        }
+        res
+    }
+
+    pub async fn confirmation_state(&self, id: Base64) -> Result<ConfirmationState, Error> {
+        self.tx_client.get_confirmation_state(id).await
+    }
+
+    /// Polls the status of a transaction until it reaches `confirmations` confirmations,
+    /// defaulting to [`CONFIRMATION_THRESHOLD`] when `None`. Gives up with
+    /// [`Error::ConfirmationTimeout`] carrying the last seen state once the configured
+    /// [`ConfirmationPolling::max_attempts`] is reached.
+    pub async fn wait_for_confirmation(
+        &self,
+        id: Base64,
+        confirmations: Option<u64>,
+    ) -> Result<TxStatus, Error> {
+        let threshold = confirmations.unwrap_or(CONFIRMATION_THRESHOLD);
+        let polling = &self.confirmation_polling;
+        let mut interval = polling.initial_interval;
+        let mut attempts = 0;
+        loop {
+            let state = match self.confirmation_state(id.clone()).await? {
+                ConfirmationState::Confirmed(status)
+                    if status.number_of_confirmations >= threshold =>
+                {
+                    return Ok(status)
+                }
+                state => state,
+            };
+
+            attempts += 1;
+            if polling.max_attempts.is_some_and(|max| attempts >= max) {
+                return Err(Error::ConfirmationTimeout(state));
+            }
+            Delay::new(interval).await;
+            interval = (interval * 2).min(polling.max_interval);
+        }
+    }
+
+    /// Downloads the transaction data chunk by chunk into the file at `path`.
+    pub async fn download_tx_data_to_file(&self, id: Base64, path: PathBuf) -> Result<(), Error> {
+        self.downloader.download_tx_data_to_file(&id, path).await
+    }
+
+    /// Discovers up to `peer_count` peers of the gateway that respond to `/info` within
+    /// [`consts::PEER_HEALTH_CHECK_TIMEOUT`] seconds.
+    pub async fn find_peers(&self, peer_count: usize) -> Result<Vec<url::Url>, Error> {
+        let peers = self
+            .network_client(self.base_url.clone())
+            .peer_urls()
+            .await
+            .map_err(|err| Error::NetworkInfoError(err.to_string()))?;
+
+        let healthy = stream::iter(peers)
+            .map(|peer| async move {
+                let client = self.network_client(peer.clone());
+                let check = client.network_info();
+                let timeout = Delay::new(Duration::from_secs(consts::PEER_HEALTH_CHECK_TIMEOUT));
+                let healthy = match future::select(Box::pin(check), timeout).await {
+                    future::Either::Left((info, _)) => info.is_ok(),
+                    future::Either::Right(_) => false,
+                };
+                healthy.then_some(peer)
+            })
+            .buffer_unordered(consts::PEER_HEALTH_CHECK_CONCURRENCY)
+            .filter_map(future::ready)
+            .take(peer_count)
+            .collect()
+            .await;
+        Ok(healthy)
+    }
+
+    /// Downloads the transaction data directly from up to `peer_count` peers discovered
+    /// through the gateway, writing it to `output`. Returns the number of bytes written.
+    ///
+    /// The chunks are validated against the data root served by the gateway, so the
+    /// discovered peers don't need to be trusted.
+    pub async fn download_transaction_data_auto<W: Write>(
+        &self,
+        id: Base64,
+        output: &mut W,
+        peer_count: usize,
+    ) -> Result<u64, Error> {
+        let peers = self.find_peers(peer_count).await?;
+        if peers.is_empty() {
+            return Err(Error::NetworkInfoError("no healthy peers found".to_owned()));
+        }
+        self.downloader
+            .download_tx_data_from_peers(&id, &peers, output)
+            .await
+    }
+
+    /// Queries the transaction status on each of `gateways` and returns it only if at least
+    /// `min_agree` of them agree on the block it was confirmed in.
+    pub async fn get_tx_status_quorum(
+        &self,
+        id: Base64,
+        gateways: Vec<url::Url>,
+        min_agree: usize,
+    ) -> Result<TxStatus, Error> {
+        let statuses = future::join_all(gateways.into_iter().map(|gateway| {
+            let id = id.clone();
+            let client = self.client.clone();
+            async move {
+                TxClient::new(client, gateway)?
+                    .get_tx_status(id)
+                    .await
+                    .map(|(_, status)| status)
+            }
+        }))
+        .await;
+
+        let mut confirmed: Vec<(TxStatus, usize)> = vec![];
+        for status in statuses.into_iter().flatten().flatten() {
+            match confirmed.iter_mut().find(|(s, _)| {
+                s.block_indep_hash == status.block_indep_hash
+                    && s.block_height == status.block_height
+            }) {
+                Some((_, votes)) => *votes += 1,
+                None => confirmed.push((status, 1)),
+            }
+        }
+
+        confirmed
+            .into_iter()
+            .find(|(_, votes)| *votes >= min_agree)
+            .map(|(status, _)| status)
+            .ok_or_else(|| {
+                Error::TransactionInfoError(format!(
+                    "less than {} gateways agree on the transaction status",
+                    min_agree
+                ))
+            })
+    }
+
+    /// Fetches the transaction data from the gateway `/raw/{id}` endpoint, falling back to
+    /// downloading it chunk by chunk if the gateway doesn't serve it.
+    pub async fn get_tx_data_raw(&self, id: Base64) -> Result<Vec<u8>, Error> {
+        match self.downloader.get_raw(&id).await? {
+            Some(data) => Ok(data),
+            None => self.downloader.download_tx_data(&id).await,
+        }
+    }
+
+    /// Fetches the transaction data together with the value of its `Content-Type` tag, if
+    /// any, e.g. to save the data with the right file extension.
+    pub async fn get_tx_data_with_content_type(
+        &self,
+        id: Base64,
+    ) -> Result<(Vec<u8>, Option<String>), Error> {
+        let tx = self.get_existing_tx(id).await?;
+        let content_type = tx.get_tag("Content-Type");
+        let data = self.get_data_of(tx).await?;
+        Ok((data, content_type))
+    }
+
+    async fn get_existing_tx(&self, id: Base64) -> Result<Tx, Error> {
+        match self.get_tx(id).await? {
+            (_, Some(tx)) => Ok(tx),
+            (status, None) => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    async fn get_data_of(&self, tx: Tx) -> Result<Vec<u8>, Error> {
+        // Format 1 transactions carry their data, format 2 ones are served without it.
+        if tx.data.is_empty() && tx.data_size > 0 {
+            self.get_tx_data_raw(tx.id).await
+        } else {
+            Ok(tx.data.0)
+        }
+    }
+
+    /// Fetches the data referenced by an `ar://{txid}` or `ar://{name}` uri, resolving
+    /// ArNS names through the gateway. Any path after the id is resolved by the gateway
+    /// against the path manifest.
+    pub async fn resolve_ar_uri(&self, uri: &str) -> Result<Vec<u8>, Error> {
+        let target = uri
+            .strip_prefix("ar://")
+            .filter(|target| !target.is_empty())
+            .ok_or_else(|| Error::InvalidArUri(uri.to_owned()))?;
+        let (name, path) = match target.split_once('/') {
+            Some((name, path)) => (name, Some(path)),
+            None => (target, None),
+        };
+
+        let id = match Base64::from_str(name) {
+            Ok(id) if id.0.len() == 32 => id,
+            _ => self.downloader.resolve_arns(name).await?,
+        };
+
+        let path = match path {
+            Some(path) => format!("{}/{}", id, path),
+            None => id.to_string(),
         };
+        self.downloader.get_data(&path).await
+    }
+
+    /// Queries any gateway endpoint, relative to the base url, and parses the JSON response.
+    /// Useful for endpoints that don't have a dedicated method.
+    pub async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
+        self.downloader.get_json(path).await
+    }
+
+    /// Queries any gateway endpoint, relative to the base url, and returns the response text.
+    pub async fn get_text(&self, path: &str) -> Result<String, Error> {
+        self.downloader.get_text(path).await
+    }
+
+    /// Resolves an ArNS name to the transaction id it points to.
+    pub async fn resolve_arns(&self, name: &str) -> Result<String, Error> {
+        let id = self.downloader.resolve_arns(name).await?;
+        Ok(id.to_string())
+    }
+
+    /// Returns the signer's RSA modulus, as used in the `owner` field of transactions.
+    pub fn owner(&self) -> Result<Base64, Error> {
+        let signer = self.signer()?;
+        Ok(signer.keypair_modulus())
+    }
+
+    pub fn get_pub_key(&self) -> Result<String, Error> {
+        let signer = self.signer()?;
         Ok(signer.keypair_modulus().to_string())
     }
 
     pub fn get_wallet_address(&self) -> Result<String, Error> {
-        let signer = match &self.signer {
-            Some(s) => s,
-            None => return Err(Error::NoneError("signer".to_owned())),
-        };
+        let signer = self.signer()?;
         Ok(signer.wallet_address().to_string())
     }
 
+    #[tracing::instrument(skip_all, fields(path = %file_path.display(), fee = fee))]
     pub async fn upload_file_from_path(
         &self,
         file_path: PathBuf,
         additional_tags: Vec<Tag<Base64>>,
         fee: u64,
     ) -> Result<(String, u64), Error> {
-        let mut auto_content_tag = true;
-        let mut additional_tags = additional_tags;
-
-        if let Some(content_type) = mime_guess::from_path(file_path.clone()).first() {
-            auto_content_tag = false;
-            let content_tag: Tag<Base64> =
-                Tag::from_utf8_strs("Content-Type", content_type.as_ref())?;
-            additional_tags.push(content_tag);
-        }
+        self.upload_file_with_transfer(file_path, Base64::empty(), 0, additional_tags, fee)
+            .await
+    }
 
-        let data = fs::read(file_path)?;
-        let transaction = self
-            .create_transaction(
-                Base64(b"".to_vec()),
-                additional_tags,
-                data,
-                0,
-                fee,
-                auto_content_tag,
-            )
+    /// Uploads a file and transfers `quantity` winstons to `target` in the same transaction.
+    pub async fn upload_file_with_transfer(
+        &self,
+        file_path: PathBuf,
+        target: Base64,
+        quantity: u128,
+        additional_tags: Vec<Tag<Base64>>,
+        fee: u64,
+    ) -> Result<(String, u64), Error> {
+        let signed_transaction = self
+            .sign_file_transaction(file_path, target, quantity, additional_tags, fee)
             .await?;
-        let signed_transaction = self.sign_transaction(transaction)?;
-        let (id, reward) = if signed_transaction.data.0.len() > MAX_TX_DATA as usize {
-            self.post_transaction_chunks(signed_transaction, 100)
-                .await?
-        } else {
-            self.post_transaction(&signed_transaction).await?
+        self.post_signed_transaction(signed_transaction, None, None)
+            .await
+    }
+
+    /// Returns the id of an earlier upload by this wallet of the data with `data_root`.
+    /// GraphQL can't filter transactions by data root, so only uploads carrying a
+    /// [`DATA_ROOT_TAG`] tag, such as the ones made with
+    /// [`Arweave::upload_file_from_path_dedup`], are found. Anyone can add that tag, so the
+    /// data root of the transaction found is checked as well.
+    pub async fn find_existing_by_data_root(
+        &self,
+        data_root: &Base64,
+    ) -> Result<Option<String>, Error> {
+        let owner = self.get_wallet_address()?;
+        let value = data_root.to_string();
+        let id = match self
+            .read_with_failover(|client| client.find_tx_by_tag(DATA_ROOT_TAG, &value, Some(&owner)))
+            .await?
+        {
+            Some(id) => id,
+            None => return Ok(None),
         };
 
-        Ok((id, reward))
+        let found_root = self
+            .get_known_tx_field(id.clone(), TxField::DataRoot)
+            .await?;
+        if found_root != value {
+            tracing::warn!(%id, data_root = %value, found_root, "tagged data root doesn't match");
+            return Ok(None);
+        }
+        Ok(Some(id.to_string()))
     }
 
-    async fn post_transaction_chunks(
+    /// Same as [`Arweave::upload_file_from_path`], but returns the id of an earlier upload of
+    /// the same data with a reward of 0 instead of uploading it again. The upload is tagged
+    /// with its data root so that it can be found by later calls.
+    pub async fn upload_file_from_path_dedup(
         &self,
-        signed_transaction: Tx,
-        chunks_buffer: usize,
+        file_path: PathBuf,
+        mut additional_tags: Vec<Tag<Base64>>,
+        fee: u64,
     ) -> Result<(String, u64), Error> {
-        if signed_transaction.id.0.is_empty() {
-            return Err(error::Error::UnsignedTransaction);
+        let data_root = Self::data_root_of_file(file_path.clone())?;
+        if let Some(id) = self.find_existing_by_data_root(&data_root).await? {
+            return Ok((id, 0));
         }
 
-        let transaction_with_no_data = signed_transaction.clone_with_no_data()?;
-        let (id, reward) = self.post_transaction(&transaction_with_no_data).await?;
+        additional_tags.push(Tag::from_utf8_strs(DATA_ROOT_TAG, &data_root.to_string())?);
+        self.upload_file_from_path(file_path, additional_tags, fee)
+            .await
+    }
 
-        let results: Vec<Result<usize, Error>> =
-            Self::upload_transaction_chunks_stream(self, signed_transaction, chunks_buffer)
-                .collect()
-                .await;
+    /// Same as [`Arweave::upload_file_from_path`], with the fee fetched from the gateway for
+    /// the size of the file.
+    pub async fn upload_file_auto_fee(
+        &self,
+        file_path: PathBuf,
+        additional_tags: Vec<Tag<Base64>>,
+    ) -> Result<(String, u64), Error> {
+        let byte_size = fs::metadata(&file_path)?.len();
+        let fee = self.get_fee_by_size(Base64::empty(), byte_size).await?;
+        self.upload_file_from_path(file_path, additional_tags, fee)
+            .await
+    }
+
+    /// Same as [`Arweave::transfer`], with the fee fetched from the gateway.
+    pub async fn transfer_auto_fee(
+        &self,
+        target: Base64,
+        quantity: u128,
+    ) -> Result<(String, u64), Error> {
+        let fee = self.get_fee_by_size(target.clone(), 0).await?;
+        self.transfer(target, quantity, fee).await
+    }
+
+    /// Sends `quantity` winstons to `target` with a transaction carrying no data.
+    pub async fn transfer(
+        &self,
+        target: Base64,
+        quantity: u128,
+        fee: u64,
+    ) -> Result<(String, u64), Error> {
+        let transaction = self
+            .create_transaction(target, vec![], vec![], quantity, fee, false)
+            .await?;
+        let signed_transaction = self.sign_transaction(transaction)?;
+        self.post_transaction(&signed_transaction).await
+    }
+
+    /// Same as [`Arweave::upload_file_from_path`], but stops posting chunks once `token` is
+    /// cancelled and returns [`Error::Cancelled`].
+    pub async fn upload_file_from_path_cancellable(
+        &self,
+        file_path: PathBuf,
+        additional_tags: Vec<Tag<Base64>>,
+        fee: u64,
+        token: CancellationToken,
+    ) -> Result<(String, u64), Error> {
+        let signed_transaction = self
+            .sign_file_transaction(file_path, Base64::empty(), 0, additional_tags, fee)
+            .await?;
+        self.post_signed_transaction(signed_transaction, None, Some(&token))
+            .await
+    }
+
+    /// Splits `data` into parts of `part_size` bytes and uploads each part as a separate
+    /// transaction, so that the parts get confirmed independently. Returns the id and reward
+    /// of every part, in order. See [`Arweave::create_split_transactions`] for the tags that
+    /// allow putting the parts back together with [`Arweave::reassemble_from_parts`].
+    pub async fn upload_data_split(
+        &self,
+        data: Vec<u8>,
+        part_size: usize,
+        tags: Vec<Tag<Base64>>,
+    ) -> Result<Vec<(String, u64)>, Error> {
+        let transactions = self
+            .create_split_transactions(data, part_size, tags)
+            .await?;
+        let mut results = Vec::with_capacity(transactions.len());
+        for transaction in transactions {
+            let signed_transaction = self.sign_transaction(transaction)?;
+            results.push(
+                self.post_signed_transaction(signed_transaction, None, None)
+                    .await?,
+            );
+        }
+        Ok(results)
+    }
+
+    /// Creates one transaction per `part_size` bytes of `data`. Besides `tags`, every part
+    /// is tagged with its `Part-Index`, the `Part-Total` and an `Upload-Id` shared by all
+    /// parts, plus an `App-Name` unless `tags` already has one.
+    pub async fn create_split_transactions(
+        &self,
+        data: Vec<u8>,
+        part_size: usize,
+        mut tags: Vec<Tag<Base64>>,
+    ) -> Result<Vec<Tx>, Error> {
+        if part_size == 0 {
+            return Err(Error::SplitUploadError("part size is 0".to_owned()));
+        }
+
+        let app_name = Base64(b"App-Name".to_vec());
+        if !tags.iter().any(|tag| tag.name == app_name) {
+            tags.push(Tag::from_utf8_strs("App-Name", SPLIT_UPLOAD_APP_NAME)?);
+        }
+        let upload_id = Base64(rand::random::<[u8; 32]>().to_vec()).to_string();
+        tags.push(Tag::from_utf8_strs("Upload-Id", &upload_id)?);
+
+        let part_total = data.chunks(part_size).len();
+        let mut transactions = Vec::with_capacity(part_total);
+        for (part_index, part) in data.chunks(part_size).enumerate() {
+            let mut part_tags = tags.clone();
+            part_tags.push(Tag::from_utf8_strs("Part-Index", &part_index.to_string())?);
+            part_tags.push(Tag::from_utf8_strs("Part-Total", &part_total.to_string())?);
 
-        results.into_iter().collect::<Result<Vec<usize>, Error>>()?;
+            let fee = self.get_fee(Base64::empty(), part.to_vec()).await?;
+            transactions.push(
+                self.create_transaction(Base64::empty(), part_tags, part.to_vec(), 0, fee, true)
+                    .await?,
+            );
+        }
+        Ok(transactions)
+    }
+
+    /// Downloads the parts of a split upload, in any order, and concatenates their data in
+    /// `Part-Index` order. Fails unless `ids` are exactly all parts of the same upload.
+    pub async fn reassemble_from_parts(&self, ids: Vec<Base64>) -> Result<Vec<u8>, Error> {
+        let invalid = |reason: &str| Error::SplitUploadError(reason.to_owned());
+
+        let mut parts = Vec::with_capacity(ids.len());
+        let mut upload_id = None;
+        for id in ids.iter() {
+            let tx = self.get_existing_tx(id.clone()).await?;
+            let part_index = tx
+                .get_tag("Part-Index")
+                .and_then(|i| i.parse::<usize>().ok());
+            let part_total = tx
+                .get_tag("Part-Total")
+                .and_then(|t| t.parse::<usize>().ok());
+            let (part_index, part_total) = match (part_index, part_total) {
+                (Some(part_index), Some(part_total)) => (part_index, part_total),
+                _ => return Err(invalid("missing part tags")),
+            };
+            if part_total != ids.len() {
+                return Err(invalid("wrong number of parts"));
+            }
+            let part_upload_id = tx.get_tag("Upload-Id");
+            if *upload_id.get_or_insert_with(|| part_upload_id.clone()) != part_upload_id {
+                return Err(invalid("parts of different uploads"));
+            }
+            parts.push((part_index, tx));
+        }
+
+        parts.sort_by_key(|(part_index, _)| *part_index);
+        if parts
+            .iter()
+            .enumerate()
+            .any(|(i, (part_index, _))| i != *part_index)
+        {
+            return Err(invalid("missing or duplicate parts"));
+        }
+
+        let mut data = Vec::new();
+        for (_, tx) in parts {
+            data.extend(self.get_data_of(tx).await?);
+        }
+        Ok(data)
+    }
+
+    /// Starts uploading a file in the background and returns immediately.
+    ///
+    /// The returned handle can be awaited for the result of the upload and reports progress
+    /// while chunks are posted. Must be called from within a Tokio runtime.
+    pub fn spawn_upload(
+        &self,
+        file_path: PathBuf,
+        additional_tags: Vec<Tag<Base64>>,
+        fee: u64,
+    ) -> UploadHandle {
+        let arweave = self.clone();
+        let (progress_tx, progress_rx) = watch::channel(UploadProgress::default());
+        let task = tokio::spawn(async move {
+            let signed_transaction = arweave
+                .sign_file_transaction(file_path, Base64::empty(), 0, additional_tags, fee)
+                .await?;
+            arweave
+                .post_signed_transaction(signed_transaction, Some(&progress_tx), None)
+                .await
+        });
+        UploadHandle::new(task, progress_rx)
+    }
+
+    async fn sign_file_transaction(
+        &self,
+        file_path: PathBuf,
+        target: Base64,
+        quantity: u128,
+        additional_tags: Vec<Tag<Base64>>,
+        fee: u64,
+    ) -> Result<Tx, Error> {
+        let mut auto_content_tag = true;
+        let mut additional_tags = additional_tags;
+
+        if let Some(content_type) = mime_guess::from_path(file_path.clone()).first() {
+            auto_content_tag = false;
+            let content_tag: Tag<Base64> =
+                Tag::from_utf8_strs("Content-Type", content_type.as_ref())?;
+            additional_tags.push(content_tag);
+        }
+
+        let data = fs::read(file_path)?;
+        let transaction = self
+            .create_transaction(
+                target,
+                additional_tags,
+                data,
+                quantity,
+                fee,
+                auto_content_tag,
+            )
+            .await?;
+        self.sign_transaction(transaction)
+    }
+
+    /// Posts data that fits in the transaction header, including empty data, in a single
+    /// request. Only larger data is uploaded chunk by chunk after posting the header.
+    async fn post_signed_transaction(
+        &self,
+        signed_transaction: Tx,
+        progress: Option<&watch::Sender<UploadProgress>>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<(String, u64), Error> {
+        if cancel.is_some_and(|token| token.is_cancelled()) {
+            return Err(Error::Cancelled);
+        }
+
+        if signed_transaction.data.0.len() > MAX_TX_DATA as usize {
+            self.post_transaction_chunks(signed_transaction, 100, progress, cancel)
+                .await
+        } else {
+            let total_chunks = signed_transaction.chunks.len();
+            let res = self.post_transaction(&signed_transaction).await?;
+            if let Some(progress) = progress {
+                progress.send_replace(UploadProgress {
+                    uploaded_chunks: total_chunks,
+                    total_chunks,
+                });
+            }
+            Ok(res)
+        }
+    }
+
+    #[tracing::instrument(
+        skip_all,
+        fields(id = %signed_transaction.id, chunks = signed_transaction.chunks.len())
+    )]
+    async fn post_transaction_chunks(
+        &self,
+        signed_transaction: Tx,
+        chunks_buffer: usize,
+        progress: Option<&watch::Sender<UploadProgress>>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<(String, u64), Error> {
+        if signed_transaction.id.0.is_empty() {
+            return Err(error::Error::UnsignedTransaction);
+        }
+
+        let mut signed_transaction = signed_transaction;
+        let transaction_with_no_data = signed_transaction.clone_with_no_data()?;
+        let (id, reward) = match self.post_transaction(&transaction_with_no_data).await {
+            // The anchor may expire while a large upload is prepared, so refresh it and
+            // re-sign, which also changes the transaction id.
+            Err(Error::StaleAnchor) => {
+                signed_transaction.last_tx = self.get_last_tx().await?;
+                signed_transaction = self.sign_transaction(signed_transaction)?;
+                let transaction_with_no_data = signed_transaction.clone_with_no_data()?;
+                self.post_transaction(&transaction_with_no_data).await?
+            }
+            res => res?,
+        };
+
+        let total_chunks = signed_transaction.chunks.len();
+        let cancelled = cancel.cloned().map(CancellationToken::cancelled_owned);
+        // Stops at the first chunk that can't be posted, dropping the chunks in flight.
+        let posted =
+            Self::upload_transaction_chunks_stream(self, signed_transaction, chunks_buffer)
+                .take_until(async move {
+                    match cancelled {
+                        Some(cancelled) => cancelled.await,
+                        None => future::pending().await,
+                    }
+                })
+                .enumerate()
+                .map(|(uploaded, res)| {
+                    if let (Some(progress), Ok(_)) = (progress, &res) {
+                        progress.send_replace(UploadProgress {
+                            uploaded_chunks: uploaded + 1,
+                            total_chunks,
+                        });
+                    }
+                    res
+                })
+                .try_collect::<Vec<usize>>()
+                .await?;
+
+        // A cancellation arriving after the last chunk was posted doesn't undo the upload.
+        if posted.len() < total_chunks && cancel.is_some_and(|token| token.is_cancelled()) {
+            return Err(Error::Cancelled);
+        }
 
         Ok((id, reward))
     }
@@ -256,13 +1242,17 @@ impl Arweave {
         signed_transaction: Tx,
         buffer: usize,
     ) -> impl Stream<Item = Result<usize, Error>> + '_ {
-        let client = Client::new();
+        let budget = Arc::new(arweave.uploader.retry_budget());
         stream::iter(0..signed_transaction.chunks.len())
             .map(move |i| {
                 let chunk = signed_transaction.get_chunk(i).unwrap(); //TODO: remove this unwrap
-                arweave
-                    .uploader
-                    .post_chunk_with_retries(chunk, client.clone())
+                let budget = budget.clone();
+                async move {
+                    arweave
+                        .uploader
+                        .post_chunk_with_budget(chunk, &budget)
+                        .await
+                }
             })
             .buffer_unordered(buffer)
     }
@@ -270,9 +1260,52 @@ impl Arweave {
 
 #[cfg(test)]
 mod tests {
-    use std::{fs::File, io::Read, str::FromStr};
+    use std::{
+        fs,
+        fs::File,
+        io::Read,
+        path::PathBuf,
+        str::FromStr,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use futures_timer::Delay;
+    use httpmock::{
+        Method::{GET, POST},
+        Mock, MockServer,
+    };
+    use serde::Deserialize;
+    use serde_json::json;
+    use tokio::sync::watch;
+    use tokio_test::block_on;
+    use tokio_util::sync::CancellationToken;
+    use tracing::{span, Subscriber};
+    use tracing_subscriber::{
+        layer::{Context, SubscriberExt},
+        Layer,
+    };
 
-    use crate::{error::Error, transaction::Tx, verify::verify_transaction};
+    use crate::{
+        consts::{
+            CONFIRMATION_THRESHOLD, MAX_TX_DATA, PEER_HEALTH_CHECK_CONCURRENCY,
+            PEER_HEALTH_CHECK_TIMEOUT,
+        },
+        crypto::base64::Base64,
+        error::Error,
+        test_utils::{
+            block_json, chunk_tree, info_json, mock_anchor, mock_data_root, mock_data_size,
+            mock_info, mock_post_tx, test_arweave, ANCHOR, REBAR3, REBAR3_DATA_ROOT, TEST_WALLET,
+        },
+        transaction::{
+            tags::{FromUtf8Strs, Tag},
+            Tx,
+        },
+        types::{ConfirmationState, TxField, VerificationCheck},
+        upload::{UploadProgress, Uploader},
+        verify::verify_transaction,
+        Arweave, ArweaveBuilder, ConfirmationPolling,
+    };
 
     #[test]
     pub fn should_parse_and_verify_valid_tx() -> Result<(), Error> {
@@ -286,4 +1319,1707 @@ mod tests {
             Err(_) => Err(Error::InvalidSignature),
         }
     }
+
+    #[test]
+    fn should_post_transfer() {
+        let target = "PAgdonEn9f5xd-UbYdCX40Sj28eltQVnxz6bbUijeVY";
+        let server = MockServer::start();
+        mock_anchor(&server);
+        let tx_mock = server.mock(|when, then| {
+            when.method(POST).path("/tx").json_body_partial(
+                json!({
+                    "target": target,
+                    "quantity": "1000000",
+                    "data_size": "0",
+                    "data_root": "",
+                    "tags": [],
+                })
+                .to_string(),
+            );
+            then.status(200);
+        });
+
+        let arweave = test_arweave(&server);
+        let (id, reward) =
+            block_on(arweave.transfer(Base64::from_str(target).unwrap(), 1_000_000, 42)).unwrap();
+
+        tx_mock.assert();
+        assert!(!id.is_empty());
+        assert_eq!(reward, 42);
+    }
+
+    #[test]
+    fn should_transfer_with_fetched_fee() {
+        let target = "PAgdonEn9f5xd-UbYdCX40Sj28eltQVnxz6bbUijeVY";
+        let server = MockServer::start();
+        mock_anchor(&server);
+        let price_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/price/0/{}", target));
+            then.status(200).body("65595508");
+        });
+        let tx_mock = server.mock(|when, then| {
+            when.method(POST).path("/tx").json_body_partial(
+                json!({
+                    "target": target,
+                    "quantity": "1000000",
+                    "reward": "65595508",
+                })
+                .to_string(),
+            );
+            then.status(200);
+        });
+
+        let arweave = test_arweave(&server);
+        let (_, reward) =
+            block_on(arweave.transfer_auto_fee(Base64::from_str(target).unwrap(), 1_000_000))
+                .unwrap();
+
+        price_mock.assert();
+        tx_mock.assert();
+        assert_eq!(reward, 65595508);
+    }
+
+    #[test]
+    fn should_upload_file_with_fetched_fee() {
+        let size = fs::metadata(REBAR3).unwrap().len();
+        let server = MockServer::start();
+        mock_anchor(&server);
+        let price_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/price/{}", size));
+            then.status(200).body("1500000000");
+        });
+        let tx_mock = server.mock(|when, then| {
+            when.method(POST).path("/tx").json_body_partial(
+                json!({
+                    "data_root": REBAR3_DATA_ROOT,
+                    "reward": "1500000000",
+                })
+                .to_string(),
+            );
+            then.status(200);
+        });
+
+        let arweave = test_arweave(&server);
+        let (_, reward) =
+            block_on(arweave.upload_file_auto_fee(PathBuf::from(REBAR3), vec![])).unwrap();
+
+        price_mock.assert();
+        tx_mock.assert();
+        assert_eq!(reward, 1500000000);
+    }
+
+    #[test]
+    fn should_upload_file_with_transfer() {
+        let target = "PAgdonEn9f5xd-UbYdCX40Sj28eltQVnxz6bbUijeVY";
+        let server = MockServer::start();
+        let anchor_mock = mock_anchor(&server);
+        let tx_mock = server.mock(|when, then| {
+            when.method(POST).path("/tx").json_body_partial(
+                json!({
+                    "target": target,
+                    "quantity": "1000000",
+                    "data_root": REBAR3_DATA_ROOT,
+                })
+                .to_string(),
+            );
+            then.status(200);
+        });
+
+        let arweave = test_arweave(&server);
+        let (_, reward) = block_on(arweave.upload_file_with_transfer(
+            PathBuf::from(REBAR3),
+            Base64::from_str(target).unwrap(),
+            1_000_000,
+            vec![],
+            42,
+        ))
+        .unwrap();
+
+        anchor_mock.assert();
+        tx_mock.assert();
+        assert_eq!(reward, 42);
+    }
+
+    #[test]
+    fn should_rebroadcast_dropped_transaction() {
+        let server = MockServer::start();
+        mock_anchor(&server);
+        let tx_mock = mock_post_tx(&server);
+
+        let arweave = test_arweave(&server);
+        let tx = block_on(arweave.create_transaction(
+            Base64::empty(),
+            vec![],
+            b"rebroadcast".to_vec(),
+            0,
+            42,
+            true,
+        ))
+        .unwrap();
+        let signed_tx = arweave.sign_transaction(tx).unwrap();
+        let (id, _) = block_on(arweave.post_transaction(&signed_tx)).unwrap();
+
+        let status_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/status", id));
+            then.status(404);
+        });
+        assert!(block_on(arweave.get_tx_status(signed_tx.id.clone())).is_err());
+        status_mock.assert();
+
+        let (rebroadcast_id, _) = block_on(arweave.rebroadcast(&signed_tx)).unwrap();
+        assert_eq!(rebroadcast_id, id);
+        tx_mock.assert_hits(2);
+    }
+
+    #[test]
+    fn should_rebroadcast_chunked_transaction() {
+        let server = MockServer::start();
+        mock_anchor(&server);
+        let header_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx")
+                .json_body_partial(json!({ "data": "" }).to_string());
+            then.status(200);
+        });
+        let chunk_mock = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200);
+        });
+
+        let arweave = test_arweave(&server);
+        let data = vec![1; MAX_TX_DATA as usize + 1];
+        let tx = block_on(arweave.create_transaction(Base64::empty(), vec![], data, 0, 42, false))
+            .unwrap();
+        let signed_tx = arweave.sign_transaction(tx).unwrap();
+
+        let (id, _) = block_on(arweave.rebroadcast(&signed_tx)).unwrap();
+        assert_eq!(id, signed_tx.id.to_string());
+        header_mock.assert();
+        chunk_mock.assert_hits(signed_tx.chunks.len());
+    }
+
+    #[test]
+    fn should_not_rebroadcast_unsigned_transaction() {
+        let server = MockServer::start();
+        let tx_mock = mock_post_tx(&server);
+
+        let arweave = test_arweave(&server);
+        let res = block_on(arweave.rebroadcast(&Tx::default()));
+        assert!(matches!(res, Err(Error::UnsignedTransaction)));
+        tx_mock.assert_hits(0);
+    }
+
+    fn mock_tx_status(server: &MockServer, id: &Base64, confirmations: u64) {
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/status", id));
+            then.status(200).json_body(json!({
+                "block_height": 1000,
+                "block_indep_hash": ANCHOR,
+                "number_of_confirmations": confirmations,
+            }));
+        });
+    }
+
+    #[test]
+    fn should_wait_for_default_confirmation_threshold() {
+        let id = Base64(vec![1; 32]);
+        let server = MockServer::start();
+        mock_tx_status(&server, &id, CONFIRMATION_THRESHOLD);
+
+        let arweave = test_arweave(&server);
+        let status = block_on(arweave.wait_for_confirmation(id, None)).unwrap();
+        assert_eq!(status.number_of_confirmations, CONFIRMATION_THRESHOLD);
+    }
+
+    #[test]
+    fn should_honor_confirmation_threshold_override() {
+        let id = Base64(vec![1; 32]);
+        let server = MockServer::start();
+        mock_tx_status(&server, &id, 5);
+
+        let arweave = test_arweave(&server);
+        let rt = tokio::runtime::Runtime::new().unwrap();
+
+        let status = rt
+            .block_on(arweave.wait_for_confirmation(id.clone(), Some(5)))
+            .unwrap();
+        assert_eq!(status.number_of_confirmations, 5);
+
+        // Below the default threshold, so waiting without an override keeps polling.
+        let res = rt.block_on(async {
+            tokio::time::timeout(
+                Duration::from_millis(500),
+                arweave.wait_for_confirmation(id, None),
+            )
+            .await
+        });
+        assert!(res.is_err());
+    }
+
+    fn fast_polling(max_attempts: Option<u32>) -> ConfirmationPolling {
+        ConfirmationPolling {
+            initial_interval: Duration::from_millis(10),
+            max_interval: Duration::from_millis(20),
+            max_attempts,
+        }
+    }
+
+    #[test]
+    fn should_track_confirmation_state_transitions() {
+        let id = Base64(vec![1; 32]);
+        let server = MockServer::start();
+        let mut arweave = test_arweave(&server);
+        arweave.confirmation_polling = fast_polling(Some(1));
+
+        let mut status_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/status", id));
+            then.status(404);
+        });
+        let state = block_on(arweave.confirmation_state(id.clone())).unwrap();
+        assert!(matches!(state, ConfirmationState::NotFound));
+        let res = block_on(arweave.wait_for_confirmation(id.clone(), None));
+        assert!(matches!(
+            res,
+            Err(Error::ConfirmationTimeout(ConfirmationState::NotFound))
+        ));
+
+        status_mock.delete();
+        status_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/status", id));
+            then.status(202);
+        });
+        let state = block_on(arweave.confirmation_state(id.clone())).unwrap();
+        assert!(matches!(state, ConfirmationState::Pending));
+        let res = block_on(arweave.wait_for_confirmation(id.clone(), None));
+        assert!(matches!(
+            res,
+            Err(Error::ConfirmationTimeout(ConfirmationState::Pending))
+        ));
+
+        status_mock.delete();
+        mock_tx_status(&server, &id, CONFIRMATION_THRESHOLD);
+        let state = block_on(arweave.confirmation_state(id.clone())).unwrap();
+        assert!(matches!(state, ConfirmationState::Confirmed(_)));
+        let status = block_on(arweave.wait_for_confirmation(id, None)).unwrap();
+        assert_eq!(status.number_of_confirmations, CONFIRMATION_THRESHOLD);
+    }
+
+    #[test]
+    fn should_stop_polling_after_max_attempts() {
+        let id = Base64(vec![1; 32]);
+        let server = MockServer::start();
+        let status_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/status", id));
+            then.status(202);
+        });
+        let mut arweave = test_arweave(&server);
+        arweave.confirmation_polling = fast_polling(Some(3));
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let res = rt.block_on(arweave.wait_for_confirmation(id, None));
+
+        assert!(matches!(
+            res,
+            Err(Error::ConfirmationTimeout(ConfirmationState::Pending))
+        ));
+        status_mock.assert_hits(3);
+    }
+
+    #[test]
+    fn should_reanchor_chunked_upload_on_stale_anchor() {
+        let new_anchor = "V6YjG8G3he0JIIwRtzTccX39rS0jH-jOqUJy6rxrVAHY0RT0AVhG8K22wCDxy1A0";
+        let server = MockServer::start();
+        let mut anchor_mock = mock_anchor(&server);
+        let stale_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx")
+                .json_body_partial(json!({ "last_tx": ANCHOR }).to_string());
+            then.status(400).body("Invalid anchor (last_tx).");
+        });
+        let fresh_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx")
+                .json_body_partial(json!({ "last_tx": new_anchor }).to_string());
+            then.status(200);
+        });
+        let chunk_mock = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200);
+        });
+
+        let arweave = test_arweave(&server);
+        let data = fs::read(REBAR3).unwrap();
+        let tx = block_on(arweave.create_transaction(Base64::empty(), vec![], data, 0, 42, true))
+            .unwrap();
+        let signed_tx = arweave.sign_transaction(tx).unwrap();
+        let stale_id = signed_tx.id.to_string();
+        let chunks = signed_tx.chunks.len();
+
+        anchor_mock.delete();
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(new_anchor);
+        });
+
+        let (id, _) = block_on(arweave.post_transaction_chunks(signed_tx, 10, None, None)).unwrap();
+
+        stale_mock.assert();
+        fresh_mock.assert();
+        chunk_mock.assert_hits(chunks);
+        assert_ne!(id, stale_id);
+    }
+
+    #[test]
+    fn should_compute_data_root_of_file() {
+        let data_root = Arweave::data_root_of_file(PathBuf::from(REBAR3)).unwrap();
+        assert_eq!(data_root.to_string(), REBAR3_DATA_ROOT);
+
+        let data = fs::read(REBAR3).unwrap();
+        assert_eq!(Arweave::data_root_of_bytes(&data).unwrap(), data_root);
+        assert!(Arweave::data_root_of_bytes(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn should_verify_transactions_concurrently() {
+        let data = fs::read_to_string("res/sample_tx.json").unwrap();
+        let mut tampered = Tx::from_str(&data).unwrap();
+        tampered.reward += 1;
+        let txs = vec![
+            Tx::from_str(&data).unwrap(),
+            tampered,
+            Tx::from_str(&data).unwrap(),
+            Tx::default(),
+            Tx::from_str(&data).unwrap(),
+        ];
+
+        let results = Arweave::verify_transactions(&txs, 2);
+
+        assert_eq!(results.len(), txs.len());
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(Error::InvalidSignature)));
+        assert!(results[2].is_ok());
+        assert!(matches!(results[3], Err(Error::UnsignedTransaction)));
+        assert!(results[4].is_ok());
+    }
+
+    #[test]
+    fn should_follow_redirects_when_fetching_tx() {
+        let data = fs::read_to_string("res/sample_tx.json").unwrap();
+        let tx = Tx::from_str(&data).unwrap();
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", tx.id));
+            then.status(302)
+                .header("location", server.url(format!("/mirror/tx/{}", tx.id)));
+        });
+        let mirror = server.mock(|when, then| {
+            when.method(GET).path(format!("/mirror/tx/{}", tx.id));
+            then.status(200).body(&data);
+        });
+
+        let arweave = test_arweave(&server);
+        let (status, fetched) = block_on(arweave.get_tx(tx.id.clone())).unwrap();
+
+        mirror.assert();
+        assert_eq!(status, reqwest::StatusCode::OK);
+        assert_eq!(fetched.unwrap().id, tx.id);
+    }
+
+    #[test]
+    fn should_follow_redirects_when_fetching_data() {
+        let id = Base64(vec![1; 32]);
+        let server = MockServer::start();
+        let redirect_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/raw/{}", id));
+            then.status(302)
+                .header("location", server.url(format!("/{}/index.html", id)));
+        });
+        let target_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/{}/index.html", id));
+            then.status(200).body("<html></html>");
+        });
+        let builder = || {
+            ArweaveBuilder::new()
+                .base_url(url::Url::parse(&server.url("/")).unwrap())
+                .keypair_path(PathBuf::from(TEST_WALLET))
+        };
+
+        let arweave = builder().build().unwrap();
+        let data = block_on(arweave.get_tx_data_raw(id.clone())).unwrap();
+        assert_eq!(data, b"<html></html>".to_vec());
+        target_mock.assert();
+
+        let arweave = builder().max_redirects(0).build().unwrap();
+        assert!(matches!(
+            block_on(arweave.get_tx_data_raw(id)),
+            Err(Error::TransactionInfoError(_))
+        ));
+        redirect_mock.assert_hits(2);
+        target_mock.assert_hits(1);
+    }
+
+    #[test]
+    fn should_use_shared_client_for_network_and_oracle() {
+        let server = MockServer::start();
+        let info_mock = server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(302)
+                .header("location", server.url("/moved/info"));
+        });
+        let oracle_mock = server.mock(|when, then| {
+            when.method(GET).path("/oracle");
+            then.status(302)
+                .header("location", server.url("/moved/oracle"));
+        });
+        let moved_mock = server.mock(|when, then| {
+            when.method(GET).path_contains("/moved/");
+            then.status(200).body("{}");
+        });
+
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("/")).unwrap())
+            .oracle_url(url::Url::parse(&server.url("/oracle")).unwrap())
+            .max_redirects(0)
+            .build()
+            .unwrap();
+        assert!(block_on(arweave.network_info()).is_err());
+        assert!(block_on(arweave.get_oracle_price()).is_err());
+
+        info_mock.assert();
+        oracle_mock.assert();
+        moved_mock.assert_hits(0);
+    }
+
+    #[test]
+    fn should_estimate_upload() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/price/1000");
+            then.status(200).body("1500000000000");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/oracle");
+            then.status(200)
+                .json_body(json!({ "arweave": { "usd": 10.0 } }));
+        });
+
+        let mut arweave = test_arweave(&server);
+        arweave.oracle_url = url::Url::parse(&server.url("/oracle")).unwrap();
+        let estimate = block_on(arweave.estimate_upload(1000)).unwrap();
+
+        assert_eq!(estimate.winston_fee, 1_500_000_000_000);
+        assert_eq!(estimate.ar, 1.5);
+        assert_eq!(estimate.usd, Some(15.0));
+    }
+
+    #[test]
+    fn should_estimate_upload_without_oracle() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/price/1000");
+            then.status(200).body("1500000000000");
+        });
+
+        let mut arweave = test_arweave(&server);
+        arweave.oracle_url = url::Url::parse(&server.url("/oracle")).unwrap();
+        let estimate = block_on(arweave.estimate_upload(1000)).unwrap();
+
+        assert_eq!(estimate.winston_fee, 1_500_000_000_000);
+        assert_eq!(estimate.usd, None);
+    }
+
+    #[test]
+    fn should_fetch_network_info() {
+        let server = MockServer::start();
+        let mock = mock_info(&server, 1_000_000, ANCHOR);
+
+        let arweave = test_arweave(&server);
+        let info = block_on(arweave.network_info()).unwrap();
+
+        mock.assert();
+        assert_eq!(info.network, "arweave.N.1");
+        assert_eq!(info.height, 1000000);
+        assert_eq!(info.current.to_string(), ANCHOR);
+    }
+
+    #[test]
+    fn should_sort_tags_before_signing() {
+        let server = MockServer::start();
+        let mut arweave = test_arweave(&server);
+        let tags = vec![
+            Tag::from_utf8_strs("b", "1").unwrap(),
+            Tag::from_utf8_strs("a", "1").unwrap(),
+        ];
+        let owner = arweave.signer.as_ref().unwrap().keypair_modulus();
+        let tx = || Tx {
+            format: 2,
+            owner: owner.clone(),
+            tags: tags.clone(),
+            ..Default::default()
+        };
+
+        let unsorted = arweave.sign_transaction(tx()).unwrap();
+        assert_eq!(unsorted.tags, tags);
+
+        arweave.sort_tags = true;
+        let sorted = arweave.sign_transaction(tx()).unwrap();
+        assert_eq!(sorted.tags, vec![tags[1].clone(), tags[0].clone()]);
+        assert!(verify_transaction(&sorted).is_ok());
+        assert_ne!(sorted.id, unsorted.id);
+    }
+
+    fn mock_gateway_status(block_indep_hash: &str) -> MockServer {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path_contains("/status");
+            then.status(200).json_body(json!({
+                "block_height": 1000,
+                "block_indep_hash": block_indep_hash,
+                "number_of_confirmations": 20,
+            }));
+        });
+        server
+    }
+
+    #[test]
+    fn should_get_tx_status_quorum() {
+        let fork = "V6YjG8G3he0JIIwRtzTccX39rS0jH-jOqUJy6rxrVAHY0RT0AVhG8K22wCDxy1A0";
+        let gateways = [
+            mock_gateway_status(ANCHOR),
+            mock_gateway_status(fork),
+            mock_gateway_status(ANCHOR),
+        ];
+        let urls: Vec<url::Url> = gateways
+            .iter()
+            .map(|server| url::Url::parse(&server.url("/")).unwrap())
+            .collect();
+        let arweave = test_arweave(&gateways[0]);
+        let id = Base64(vec![1; 32]);
+
+        let status = block_on(arweave.get_tx_status_quorum(id.clone(), urls.clone(), 2)).unwrap();
+        assert_eq!(status.block_indep_hash.to_string(), ANCHOR);
+
+        let res = block_on(arweave.get_tx_status_quorum(id, urls, 3));
+        assert!(matches!(res, Err(Error::TransactionInfoError(_))));
+    }
+
+    #[test]
+    fn should_get_tx_data_raw() {
+        let id = Base64(vec![1; 32]);
+        let server = MockServer::start();
+        let raw_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/raw/{}", id));
+            then.status(200).body("raw data");
+        });
+        let offset_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", id));
+            then.status(200);
+        });
+
+        let arweave = test_arweave(&server);
+        let data = block_on(arweave.get_tx_data_raw(id)).unwrap();
+
+        raw_mock.assert();
+        offset_mock.assert_hits(0);
+        assert_eq!(data, b"raw data".to_vec());
+    }
+
+    #[test]
+    fn should_fall_back_to_chunks_without_raw_endpoint() {
+        let id = Base64(vec![1; 32]);
+        let server = MockServer::start();
+        let raw_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/raw/{}", id));
+            then.status(404);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", id));
+            then.status(200)
+                .json_body(json!({ "size": "10", "offset": "1009" }));
+        });
+        let (data_root, responses) = chunk_tree(&[b"chunk data"]);
+        mock_data_root(&server, &id, &data_root);
+        mock_data_size(&server, &id, 10);
+        let chunk_mock = server.mock(|when, then| {
+            when.method(GET).path("/chunk/1000");
+            then.status(200).json_body(responses[0].clone());
+        });
+
+        let arweave = test_arweave(&server);
+        let data = block_on(arweave.get_tx_data_raw(id)).unwrap();
+
+        raw_mock.assert();
+        chunk_mock.assert();
+        assert_eq!(data, b"chunk data".to_vec());
+    }
+
+    #[test]
+    fn should_resolve_ar_uri() {
+        let id = Base64(vec![1; 32]);
+        let server = MockServer::start();
+        let data_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/{}", id));
+            then.status(200).body("data");
+        });
+
+        let arweave = test_arweave(&server);
+        let data = block_on(arweave.resolve_ar_uri(&format!("ar://{}", id))).unwrap();
+
+        data_mock.assert();
+        assert_eq!(data, b"data".to_vec());
+    }
+
+    #[test]
+    fn should_reject_invalid_ar_uri() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+
+        for uri in ["https://arweave.net", "ar://"] {
+            let res = block_on(arweave.resolve_ar_uri(uri));
+            assert!(matches!(res, Err(Error::InvalidArUri(_))));
+        }
+    }
+
+    #[test]
+    fn should_resolve_arns_name() {
+        let id = Base64(vec![1; 32]);
+        let server = MockServer::start();
+        let resolver_mock = server.mock(|when, then| {
+            when.method(GET).path("/ar-io/resolver/records/ardrive");
+            then.status(200)
+                .json_body(json!({ "txId": id.to_string(), "ttlSeconds": 3600 }));
+        });
+
+        let arweave = test_arweave(&server);
+        let resolved = block_on(arweave.resolve_arns("ardrive")).unwrap();
+
+        resolver_mock.assert();
+        assert_eq!(resolved, id.to_string());
+    }
+
+    #[test]
+    fn should_resolve_ar_uri_with_arns_name() {
+        let id = Base64(vec![1; 32]);
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/ar-io/resolver/records/ardrive");
+            then.status(200)
+                .json_body(json!({ "txId": id.to_string() }));
+        });
+        let data_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/{}/index.html", id));
+            then.status(200).body("data");
+        });
+
+        let arweave = test_arweave(&server);
+        let data = block_on(arweave.resolve_ar_uri("ar://ardrive/index.html")).unwrap();
+
+        data_mock.assert();
+        assert_eq!(data, b"data".to_vec());
+    }
+
+    #[test]
+    fn should_return_owner_as_base64() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+
+        let owner = arweave.owner().unwrap();
+        let signer = arweave.signer.as_ref().unwrap();
+        assert_eq!(owner.0, signer.get_public_key().0);
+        assert_eq!(owner.to_string(), arweave.get_pub_key().unwrap());
+    }
+
+    #[test]
+    fn should_validate_explicit_anchor() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+        let create = |anchor: Base64| {
+            arweave.create_transaction_with_anchor(
+                Base64::default(),
+                vec![],
+                b"data".to_vec(),
+                0,
+                0,
+                false,
+                anchor,
+            )
+        };
+
+        let tx = create(Base64(vec![1; 32])).unwrap();
+        assert_eq!(tx.last_tx, Base64(vec![1; 32]));
+        assert!(create(Base64::from_str(ANCHOR).unwrap()).is_ok());
+
+        assert!(matches!(
+            create(Base64(vec![1; 31])),
+            Err(Error::InvalidAnchor(31))
+        ));
+        assert!(matches!(
+            create(Base64::default()),
+            Err(Error::InvalidAnchor(0))
+        ));
+    }
+
+    #[test]
+    fn should_stop_checking_peers_once_enough_are_found() {
+        let gateway = MockServer::start();
+        let peer = MockServer::start();
+        gateway.mock(|when, then| {
+            when.method(GET).path("/peers");
+            then.status(200)
+                .json_body(json!(vec![peer.address().to_string(); 50]));
+        });
+        let info_mock = mock_info(&peer, 1000, ANCHOR);
+
+        let arweave = test_arweave(&gateway);
+        let peers = block_on(arweave.find_peers(2)).unwrap();
+
+        assert_eq!(peers.len(), 2);
+        // One more check is started to refill the buffer after the first healthy peer.
+        assert!(info_mock.hits() <= PEER_HEALTH_CHECK_CONCURRENCY + 1);
+    }
+
+    #[test]
+    fn should_treat_hanging_peer_as_unhealthy() {
+        let gateway = MockServer::start();
+        let healthy_peer = MockServer::start();
+        let hanging_peer = MockServer::start();
+        gateway.mock(|when, then| {
+            when.method(GET).path("/peers");
+            then.status(200).json_body(json!([
+                hanging_peer.address().to_string(),
+                healthy_peer.address().to_string(),
+            ]));
+        });
+        hanging_peer.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200)
+                .json_body(info_json(1000, ANCHOR))
+                .delay(Duration::from_secs(PEER_HEALTH_CHECK_TIMEOUT * 2));
+        });
+        mock_info(&healthy_peer, 1000, ANCHOR);
+
+        let arweave = test_arweave(&gateway);
+        let started = Instant::now();
+        let peers = block_on(arweave.find_peers(2)).unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(PEER_HEALTH_CHECK_TIMEOUT * 2));
+        assert_eq!(
+            peers,
+            vec![url::Url::parse(&healthy_peer.url("/")).unwrap()]
+        );
+    }
+
+    #[test]
+    fn should_download_from_discovered_peers() {
+        let id = Base64(vec![1; 32]);
+        let gateway = MockServer::start();
+        let healthy_peer = MockServer::start();
+        let unhealthy_peer = MockServer::start();
+        gateway.mock(|when, then| {
+            when.method(GET).path("/peers");
+            then.status(200).json_body(json!([
+                unhealthy_peer.address().to_string(),
+                "not a peer:port",
+                healthy_peer.address().to_string(),
+            ]));
+        });
+        unhealthy_peer.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(500);
+        });
+        mock_info(&healthy_peer, 1000, ANCHOR);
+        healthy_peer.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", id));
+            then.status(200)
+                .json_body(json!({ "size": "10", "offset": "1009" }));
+        });
+        let (data_root, responses) = chunk_tree(&[b"chunk data"]);
+        mock_data_root(&gateway, &id, &data_root);
+        mock_data_size(&gateway, &id, 10);
+        let chunk_mock = healthy_peer.mock(|when, then| {
+            when.method(GET).path("/chunk/1000");
+            then.status(200).json_body(responses[0].clone());
+        });
+
+        let arweave = test_arweave(&gateway);
+        let mut output = vec![];
+        let written = block_on(arweave.download_transaction_data_auto(id, &mut output, 1)).unwrap();
+
+        chunk_mock.assert();
+        assert_eq!(written, 10);
+        assert_eq!(output, b"chunk data".to_vec());
+    }
+
+    #[test]
+    fn should_verify_transaction_full() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+        let tx = arweave
+            .create_transaction_with_anchor(
+                Base64::default(),
+                vec![],
+                b"data".to_vec(),
+                0,
+                0,
+                false,
+                Base64::from_str(ANCHOR).unwrap(),
+            )
+            .unwrap();
+        let signed_tx = arweave.sign_transaction(tx).unwrap();
+        assert!(Arweave::verify_transaction_full(&signed_tx).is_ok());
+
+        let failed_check = |tx: &Tx| match Arweave::verify_transaction_full(tx) {
+            Err(Error::TransactionVerificationFailed(check)) => Some(check),
+            _ => None,
+        };
+
+        let mut tx = signed_tx;
+        tx.signature.0[0] ^= 1;
+        assert_eq!(failed_check(&tx), Some(VerificationCheck::Signature));
+        tx.signature.0[0] ^= 1;
+
+        tx.id.0[0] ^= 1;
+        assert_eq!(failed_check(&tx), Some(VerificationCheck::Id));
+        tx.id.0[0] ^= 1;
+
+        tx.data.0[0] ^= 1;
+        assert_eq!(failed_check(&tx), Some(VerificationCheck::DataRoot));
+    }
+
+    #[test]
+    fn should_download_tx_data_to_file() {
+        let id = Base64(vec![1; 32]);
+        let data = fs::read(REBAR3).unwrap();
+        let leaves = crate::crypto::merkle::generate_leaves(data.clone()).unwrap();
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", id));
+            then.status(200).json_body(json!({
+                "size": data.len().to_string(),
+                "offset": (999 + data.len()).to_string(),
+            }));
+        });
+        let chunks: Vec<&[u8]> = leaves
+            .iter()
+            .map(|leaf| &data[leaf.min_byte_range..leaf.max_byte_range])
+            .collect();
+        let (data_root, responses) = chunk_tree(&chunks);
+        assert_eq!(data_root.to_string(), REBAR3_DATA_ROOT);
+        mock_data_root(&server, &id, &data_root);
+        for (leaf, response) in leaves.iter().zip(responses) {
+            server.mock(|when, then| {
+                when.method(GET)
+                    .path(format!("/chunk/{}", 1000 + leaf.min_byte_range));
+                then.status(200).json_body(response);
+            });
+        }
+
+        let path = std::env::temp_dir().join(format!("arweave-rs-download-{}", id));
+        let arweave = test_arweave(&server);
+        block_on(arweave.download_tx_data_to_file(id, path.clone())).unwrap();
+
+        let downloaded = fs::read(&path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(downloaded, data);
+    }
+
+    #[test]
+    fn should_build_and_sign_without_tokio() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+
+        let signed_tx = futures::executor::block_on(async {
+            Delay::new(Duration::from_millis(1)).await;
+            let tx = arweave.create_transaction_with_anchor(
+                Base64::default(),
+                vec![],
+                b"data".to_vec(),
+                0,
+                0,
+                true,
+                Base64::from_str(ANCHOR).unwrap(),
+            )?;
+            arweave.sign_transaction(tx)
+        })
+        .unwrap();
+
+        assert!(Arweave::verify_transaction_full(&signed_tx).is_ok());
+    }
+
+    #[test]
+    fn should_upload_empty_and_tiny_files_in_one_request() {
+        for size in [0usize, 10] {
+            let server = MockServer::start();
+            mock_anchor(&server);
+            let tx_mock = server.mock(|when, then| {
+                when.method(POST).path("/tx").json_body_partial(
+                    json!({
+                        "data_size": size.to_string(),
+                        "data": Base64(vec![b'a'; size]).to_string(),
+                    })
+                    .to_string(),
+                );
+                then.status(200);
+            });
+            let chunk_mock = server.mock(|when, then| {
+                when.method(POST).path("/chunk");
+                then.status(200);
+            });
+
+            let path = std::env::temp_dir().join(format!("arweave-rs-upload-{}.txt", size));
+            fs::write(&path, vec![b'a'; size]).unwrap();
+            let arweave = test_arweave(&server);
+            let res = block_on(arweave.upload_file_from_path(path.clone(), vec![], 42));
+            fs::remove_file(path).unwrap();
+
+            res.unwrap();
+            tx_mock.assert();
+            chunk_mock.assert_hits(0);
+        }
+    }
+
+    #[test]
+    fn should_fail_over_to_fallback_gateway() {
+        let address = "ggHWyKn0I_CTtsyyt2OR85sPYz9OvKLd9DYIvRQ2ET4";
+        let primary = MockServer::start();
+        let secondary = MockServer::start();
+        let primary_mock = primary.mock(|when, then| {
+            when.method(GET);
+            then.status(503);
+        });
+        secondary.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/wallet/{}/balance", address));
+            then.status(200).body("1000");
+        });
+        secondary.mock(|when, then| {
+            when.method(GET).path("/price/4");
+            then.status(200).body("42");
+        });
+
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&primary.url("/")).unwrap())
+            .fallback_gateways(vec![url::Url::parse(&secondary.url("/")).unwrap()])
+            .oracle_url(url::Url::parse(&secondary.url("/oracle")).unwrap())
+            .build()
+            .unwrap();
+
+        assert_eq!(block_on(arweave.get_balance(address)).unwrap(), "1000");
+        let fee = block_on(arweave.get_fee(Base64::empty(), b"data".to_vec())).unwrap();
+        assert_eq!(fee, 42);
+        let estimate = block_on(arweave.estimate_upload(4)).unwrap();
+        assert_eq!(estimate.winston_fee, 42);
+        primary_mock.assert_hits(3);
+    }
+
+    #[test]
+    fn should_not_fail_over_on_client_errors() {
+        let id = Base64(vec![1; 32]);
+        let primary = MockServer::start();
+        let secondary = MockServer::start();
+        primary.mock(|when, then| {
+            when.method(GET);
+            then.status(404);
+        });
+        let secondary_mock = secondary.mock(|when, then| {
+            when.method(GET);
+            then.status(200);
+        });
+
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&primary.url("/")).unwrap())
+            .fallback_gateways(vec![url::Url::parse(&secondary.url("/")).unwrap()])
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            block_on(arweave.get_tx_status(id)),
+            Err(Error::TransactionInfoError(_))
+        ));
+        secondary_mock.assert_hits(0);
+    }
+
+    #[tokio::test]
+    async fn should_spawn_upload_and_await_handle() {
+        let server = MockServer::start();
+        mock_anchor(&server);
+        let tx_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx")
+                .json_body_partial(json!({ "data_root": REBAR3_DATA_ROOT }).to_string());
+            then.status(200);
+        });
+
+        let arweave = test_arweave(&server);
+        let handle = arweave.spawn_upload(PathBuf::from(REBAR3), vec![], 42);
+        let progress = handle.progress();
+        let (_, reward) = handle.await.unwrap();
+
+        tx_mock.assert();
+        assert_eq!(reward, 42);
+        let progress = *progress.borrow();
+        assert!(progress.total_chunks > 0);
+        assert_eq!(progress.uploaded_chunks, progress.total_chunks);
+    }
+
+    #[test]
+    fn should_query_custom_gateway_endpoints() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Field {
+            name: String,
+            size: u64,
+        }
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/custom/field.json");
+            then.status(200)
+                .json_body(json!({ "name": "data", "size": 42 }));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/custom/field.txt");
+            then.status(200).body("plain text");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/custom/missing");
+            then.status(404);
+        });
+
+        let arweave = test_arweave(&server);
+        let field: Field = block_on(arweave.get_json("custom/field.json")).unwrap();
+        assert_eq!(
+            field,
+            Field {
+                name: "data".to_owned(),
+                size: 42
+            }
+        );
+        assert_eq!(
+            block_on(arweave.get_text("custom/field.txt")).unwrap(),
+            "plain text"
+        );
+        assert!(matches!(
+            block_on(arweave.get_json::<Field>("custom/field.txt")),
+            Err(Error::SerdeJsonError(_))
+        ));
+        assert!(matches!(
+            block_on(arweave.get_text("custom/missing")),
+            Err(Error::TransactionInfoError(_))
+        ));
+    }
+
+    #[test]
+    fn should_get_tx_field() {
+        let id = Base64(vec![1; 32]);
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/data_root", id));
+            then.status(200).body(REBAR3_DATA_ROOT);
+        });
+
+        let arweave = test_arweave(&server);
+        let data_root = block_on(arweave.get_tx_field(id.clone(), "data_root")).unwrap();
+        assert_eq!(data_root, REBAR3_DATA_ROOT);
+        assert!(Base64::from_str(&data_root).is_ok());
+
+        let data_root = block_on(arweave.get_known_tx_field(id, TxField::DataRoot)).unwrap();
+        assert_eq!(data_root, REBAR3_DATA_ROOT);
+        mock.assert_hits(2);
+    }
+
+    #[test]
+    fn should_verify_downloaded_data() {
+        let id = Base64(vec![1; 32]);
+        let data_root = Arweave::data_root_of_bytes(b"hello world").unwrap();
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/data_root", id));
+            then.status(200).body(data_root.to_string());
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", id));
+            then.status(200)
+                .json_body(json!({ "size": "11", "offset": "1010" }));
+        });
+        mock_data_size(&server, &id, 11);
+        let (_, responses) = chunk_tree(&[b"hello world"]);
+        server.mock(|when, then| {
+            when.method(GET).path("/chunk/1000");
+            then.status(200).json_body(responses[0].clone());
+        });
+
+        let arweave = test_arweave(&server);
+        let mut data = block_on(arweave.downloader.download_tx_data(&id)).unwrap();
+        assert!(block_on(arweave.verify_downloaded_data(id.clone(), &data)).is_ok());
+
+        data[0] ^= 1;
+        assert!(matches!(
+            block_on(arweave.verify_downloaded_data(id, &data)),
+            Err(Error::InvalidProof)
+        ));
+    }
+
+    fn mock_existing_upload<'a>(
+        server: &'a MockServer,
+        arweave: &Arweave,
+        id: &Base64,
+    ) -> Mock<'a> {
+        let owner = arweave.get_wallet_address().unwrap();
+        let id = id.to_string();
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/graphql")
+                .body_contains(REBAR3_DATA_ROOT)
+                .body_contains(owner);
+            then.status(200).json_body(json!({
+                "data": {
+                    "transactions": {
+                        "edges": [{ "node": { "id": id } }]
+                    }
+                }
+            }));
+        })
+    }
+
+    #[test]
+    fn should_skip_upload_of_existing_data() {
+        let existing = Base64(vec![7; 32]);
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+        let graphql_mock = mock_existing_upload(&server, &arweave, &existing);
+        let data_root_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/data_root", existing));
+            then.status(200).body(REBAR3_DATA_ROOT);
+        });
+        let tx_mock = mock_post_tx(&server);
+
+        let (id, reward) =
+            block_on(arweave.upload_file_from_path_dedup(PathBuf::from(REBAR3), vec![], 42))
+                .unwrap();
+
+        graphql_mock.assert();
+        data_root_mock.assert();
+        tx_mock.assert_hits(0);
+        assert_eq!(id, existing.to_string());
+        assert_eq!(reward, 0);
+    }
+
+    #[test]
+    fn should_upload_when_tagged_data_root_does_not_match() {
+        let existing = Base64(vec![7; 32]);
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+        mock_existing_upload(&server, &arweave, &existing);
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/data_root", existing));
+            then.status(200).body(Base64(vec![8; 32]).to_string());
+        });
+        mock_anchor(&server);
+        let tx_mock = mock_post_tx(&server);
+
+        let (id, reward) =
+            block_on(arweave.upload_file_from_path_dedup(PathBuf::from(REBAR3), vec![], 42))
+                .unwrap();
+
+        tx_mock.assert();
+        assert_ne!(id, existing.to_string());
+        assert_eq!(reward, 42);
+    }
+
+    #[test]
+    fn should_upload_new_data_with_data_root_tag() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST).path("/graphql");
+            then.status(200)
+                .json_body(json!({ "data": { "transactions": { "edges": [] } } }));
+        });
+        mock_anchor(&server);
+        let tx_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx")
+                .body_contains(Base64::from_utf8_str("Data-Root").unwrap().to_string())
+                .body_contains(Base64::from_utf8_str(REBAR3_DATA_ROOT).unwrap().to_string());
+            then.status(200);
+        });
+
+        let arweave = test_arweave(&server);
+        let (_, reward) =
+            block_on(arweave.upload_file_from_path_dedup(PathBuf::from(REBAR3), vec![], 42))
+                .unwrap();
+
+        tx_mock.assert();
+        assert_eq!(reward, 42);
+    }
+
+    #[test]
+    fn should_get_mempool_size() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/tx/pending");
+            then.status(200).json_body(json!([
+                Base64(vec![1; 32]).to_string(),
+                Base64(vec![2; 32]).to_string(),
+                Base64(vec![3; 32]).to_string(),
+            ]));
+        });
+
+        let arweave = test_arweave(&server);
+
+        assert_eq!(block_on(arweave.mempool_size()).unwrap(), 3);
+        mock.assert();
+    }
+
+    #[test]
+    fn should_get_tx_data_with_content_type() {
+        let server = MockServer::start();
+        mock_anchor(&server);
+
+        let arweave = test_arweave(&server);
+        let tags = vec![Tag::from_utf8_strs("Content-Type", "text/plain").unwrap()];
+        let tx = block_on(arweave.create_transaction(
+            Base64::empty(),
+            tags,
+            b"hello".to_vec(),
+            0,
+            42,
+            false,
+        ))
+        .unwrap();
+        let tx = arweave.sign_transaction(tx).unwrap();
+        let id = tx.id.clone();
+        let tx_json = serde_json::to_string(&tx.clone_with_no_data().unwrap()).unwrap();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", id));
+            then.status(200).body(tx_json);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/raw/{}", id));
+            then.status(200).body("hello");
+        });
+
+        let (data, content_type) = block_on(arweave.get_tx_data_with_content_type(id)).unwrap();
+
+        assert_eq!(data, b"hello".to_vec());
+        assert_eq!(content_type, Some("text/plain".to_string()));
+    }
+
+    #[test]
+    fn should_sign_and_verify_message_with_context() {
+        let arweave = ArweaveBuilder::new()
+            .keypair_path(PathBuf::from(TEST_WALLET))
+            .build()
+            .unwrap();
+        let owner = arweave.owner().unwrap();
+        let signature = arweave
+            .sign_message_with_context("login:example.com", b"nonce 42")
+            .unwrap();
+
+        assert!(Arweave::verify_message_with_context(
+            &owner.0,
+            "login:example.com",
+            b"nonce 42",
+            &signature
+        )
+        .is_ok());
+        assert!(matches!(
+            Arweave::verify_message_with_context(
+                &owner.0,
+                "login:other.com",
+                b"nonce 42",
+                &signature
+            ),
+            Err(Error::InvalidSignature)
+        ));
+        assert!(matches!(
+            Arweave::verify(&owner.0, b"nonce 42", &signature),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn should_fail_signing_without_signer() {
+        let arweave = ArweaveBuilder::new().build().unwrap();
+
+        assert!(matches!(arweave.sign(b"message"), Err(Error::NoSigner)));
+        assert!(matches!(
+            arweave.sign_transaction(Tx::default()),
+            Err(Error::NoSigner)
+        ));
+        assert!(matches!(arweave.get_wallet_address(), Err(Error::NoSigner)));
+    }
+
+    #[test]
+    fn should_detect_stale_anchor_before_posting() {
+        let fresh_anchor = "V6YjG8G3he0JIIwRtzTccX39rS0jH-jOqUJy6rxrVAHY0RT0AVhG8K22wCDxy1A0";
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(fresh_anchor);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/block/hash/{}", ANCHOR));
+            then.status(200).json_body(block_json(1_000));
+        });
+        let mut info_mock = mock_info(&server, 1_100, fresh_anchor);
+        let tx_mock = mock_post_tx(&server);
+
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("/")).unwrap())
+            .keypair_path(PathBuf::from(TEST_WALLET))
+            .check_anchor(true)
+            .build()
+            .unwrap();
+        let tx = arweave
+            .create_transaction_with_anchor(
+                Base64::empty(),
+                vec![],
+                vec![1, 2, 3],
+                0,
+                42,
+                true,
+                Base64::from_str(ANCHOR).unwrap(),
+            )
+            .unwrap();
+        let signed_tx = arweave.sign_transaction(tx).unwrap();
+
+        assert!(matches!(
+            block_on(arweave.post_transaction(&signed_tx)),
+            Err(Error::StaleAnchor)
+        ));
+        tx_mock.assert_hits(0);
+
+        // Still within the window.
+        info_mock.delete();
+        mock_info(&server, 1_010, fresh_anchor);
+        assert!(block_on(arweave.post_transaction(&signed_tx)).is_ok());
+        tx_mock.assert();
+    }
+
+    #[test]
+    fn should_report_stale_anchor_rejection() {
+        let server = MockServer::start();
+        let tx_mock = server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(400).body("Invalid anchor (last_tx).");
+        });
+
+        let arweave = test_arweave(&server);
+        let tx = arweave
+            .create_transaction_with_anchor(
+                Base64::empty(),
+                vec![],
+                vec![1, 2, 3],
+                0,
+                42,
+                true,
+                Base64::from_str(ANCHOR).unwrap(),
+            )
+            .unwrap();
+        let signed_tx = arweave.sign_transaction(tx).unwrap();
+
+        assert!(matches!(
+            block_on(arweave.post_transaction(&signed_tx)),
+            Err(Error::StaleAnchor)
+        ));
+        tx_mock.assert();
+    }
+
+    #[test]
+    fn should_verify_data_root_when_enabled() {
+        let arweave = ArweaveBuilder::new()
+            .keypair_path(PathBuf::from(TEST_WALLET))
+            .verify_data_root(true)
+            .build()
+            .unwrap();
+        assert!(arweave.verify_data_root);
+
+        let data = fs::read(REBAR3).unwrap();
+        let tx = arweave
+            .create_transaction_with_anchor(
+                Base64::empty(),
+                vec![],
+                data,
+                0,
+                42,
+                true,
+                Base64::from_str(ANCHOR).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(tx.data_root.to_string(), REBAR3_DATA_ROOT);
+    }
+
+    #[test]
+    fn should_upload_data_split_into_parts() {
+        let server = MockServer::start();
+        mock_anchor(&server);
+        for size in [4, 2] {
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/price/{}", size));
+                then.status(200).body("100");
+            });
+        }
+        let full_parts = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx")
+                .json_body_partial(json!({ "data_size": "4" }).to_string());
+            then.status(200);
+        });
+        let last_part = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx")
+                .json_body_partial(json!({ "data_size": "2" }).to_string());
+            then.status(200);
+        });
+
+        let arweave = test_arweave(&server);
+        let tags = vec![Tag::from_utf8_strs("App-Name", "arweave-rs").unwrap()];
+        let results = block_on(arweave.upload_data_split(b"0123456789".to_vec(), 4, tags)).unwrap();
+
+        full_parts.assert_hits(2);
+        last_part.assert_hits(1);
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|(_, reward)| *reward == 100));
+        assert_ne!(results[0].0, results[1].0);
+    }
+
+    #[tokio::test]
+    async fn should_stop_posting_chunks_when_cancelled() {
+        let server = MockServer::start();
+        mock_anchor(&server);
+        mock_post_tx(&server);
+        let chunk_mock = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200).delay(Duration::from_millis(200));
+        });
+
+        let arweave = test_arweave(&server);
+        let data = fs::read(REBAR3).unwrap();
+        let tx = arweave
+            .create_transaction(Base64::empty(), vec![], data, 0, 42, true)
+            .await
+            .unwrap();
+        let signed_tx = arweave.sign_transaction(tx).unwrap();
+        let chunks = signed_tx.chunks.len();
+        assert!(chunks > 2);
+
+        let token = CancellationToken::new();
+        let (progress_tx, mut progress_rx) = watch::channel(UploadProgress::default());
+        let cancel = token.clone();
+        tokio::spawn(async move {
+            while progress_rx.changed().await.is_ok() {
+                if progress_rx.borrow().uploaded_chunks >= 1 {
+                    cancel.cancel();
+                    break;
+                }
+            }
+        });
+
+        let res = arweave
+            .post_transaction_chunks(signed_tx, 1, Some(&progress_tx), Some(&token))
+            .await;
+
+        assert!(matches!(res, Err(Error::Cancelled)));
+        // The chunk already in flight when cancelling may still reach the gateway.
+        assert!(chunk_mock.hits() <= 2);
+        assert!(chunk_mock.hits() < chunks);
+    }
+
+    #[tokio::test]
+    async fn should_share_client_between_posting_and_chunk_upload() {
+        let server = MockServer::start();
+        mock_anchor(&server);
+        let tx_mock = server.mock(|when, then| {
+            when.method(POST).path("/tx").header("x-client", "shared");
+            then.status(200);
+        });
+        let chunk_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/chunk")
+                .header("x-client", "shared");
+            then.status(200);
+        });
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-client", "shared".parse().unwrap());
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()
+            .unwrap();
+        let arweave = ArweaveBuilder::new()
+            .base_url(url::Url::parse(&server.url("/")).unwrap())
+            .keypair_path(PathBuf::from(TEST_WALLET))
+            .client(client)
+            .build()
+            .unwrap();
+        let data = fs::read(REBAR3).unwrap();
+        let tx = arweave
+            .create_transaction(Base64::empty(), vec![], data, 0, 42, true)
+            .await
+            .unwrap();
+        let signed_tx = arweave.sign_transaction(tx).unwrap();
+        let chunks = signed_tx.chunks.len();
+
+        arweave
+            .post_transaction_chunks(signed_tx, 10, None, None)
+            .await
+            .unwrap();
+
+        tx_mock.assert();
+        chunk_mock.assert_hits(chunks);
+    }
+
+    /// Records the names of the spans created while it is the default subscriber.
+    #[derive(Clone, Default)]
+    struct SpanRecorder(Arc<Mutex<Vec<&'static str>>>);
+
+    impl<S: Subscriber> Layer<S> for SpanRecorder {
+        fn on_new_span(&self, attrs: &span::Attributes<'_>, _: &span::Id, _: Context<'_, S>) {
+            self.0.lock().unwrap().push(attrs.metadata().name());
+        }
+    }
+
+    #[test]
+    fn should_emit_upload_and_download_spans() {
+        let server = MockServer::start();
+        mock_anchor(&server);
+        mock_post_tx(&server);
+        server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200);
+        });
+        let id = Base64(vec![1; 32]);
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/offset", id));
+            then.status(200)
+                .json_body(json!({ "size": "5", "offset": "1004" }));
+        });
+        let (data_root, responses) = chunk_tree(&[b"hello"]);
+        mock_data_root(&server, &id, &data_root);
+        mock_data_size(&server, &id, 5);
+        server.mock(|when, then| {
+            when.method(GET).path("/chunk/1000");
+            then.status(200).json_body(responses[0].clone());
+        });
+
+        let arweave = test_arweave(&server);
+        let recorder = SpanRecorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            block_on(async {
+                arweave
+                    .upload_file_from_path(PathBuf::from(REBAR3), vec![], 42)
+                    .await
+                    .unwrap();
+
+                let data = fs::read(REBAR3).unwrap();
+                let tx = arweave
+                    .create_transaction(Base64::empty(), vec![], data, 0, 42, true)
+                    .await
+                    .unwrap();
+                let signed_tx = arweave.sign_transaction(tx).unwrap();
+                arweave
+                    .post_transaction_chunks(signed_tx, 10, None, None)
+                    .await
+                    .unwrap();
+
+                arweave.downloader.download_tx_data(&id).await.unwrap();
+            })
+        });
+
+        let spans = recorder.0.lock().unwrap();
+        for name in [
+            "upload_file_from_path",
+            "post_transaction_chunks",
+            "download_tx_data",
+        ] {
+            assert!(spans.contains(&name), "missing span {}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_abort_upload_when_retry_budget_is_used_up() {
+        let server = MockServer::start();
+        mock_anchor(&server);
+        mock_post_tx(&server);
+        let chunk_mock = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(503);
+        });
+
+        let mut arweave = test_arweave(&server);
+        arweave.uploader =
+            Uploader::new(url::Url::parse(&server.url("/")).unwrap()).with_retry_budget(2);
+        let data = fs::read(REBAR3).unwrap();
+        let tx = arweave
+            .create_transaction(Base64::empty(), vec![], data, 0, 42, true)
+            .await
+            .unwrap();
+        let signed_tx = arweave.sign_transaction(tx).unwrap();
+        let chunks = signed_tx.chunks.len();
+
+        let started = Instant::now();
+        let res = arweave
+            .post_transaction_chunks(signed_tx, chunks, None, None)
+            .await;
+
+        assert!(matches!(res, Err(Error::RetryBudgetExhausted(2))));
+        // Without a shared budget every chunk would sleep through all of its retries.
+        assert!(started.elapsed() < Duration::from_secs(3));
+        assert!(chunk_mock.hits() <= chunks + 2);
+    }
+
+    #[test]
+    fn should_reassemble_split_upload() {
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let server = MockServer::start();
+        mock_anchor(&server);
+        server.mock(|when, then| {
+            when.method(GET).path_contains("/price/");
+            then.status(200).body("100");
+        });
+
+        let arweave = test_arweave(&server);
+        let transactions =
+            block_on(arweave.create_split_transactions(data.clone(), 300, vec![])).unwrap();
+        assert_eq!(transactions.len(), 4);
+
+        let mut ids = vec![];
+        for tx in transactions {
+            let tx = arweave.sign_transaction(tx).unwrap();
+            assert_eq!(tx.get_tag("App-Name"), Some("arweave-rs".to_string()));
+            assert_eq!(tx.get_tag("Part-Total"), Some("4".to_string()));
+            let tx_json = serde_json::to_string(&tx.clone_with_no_data().unwrap()).unwrap();
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/tx/{}", tx.id));
+                then.status(200).body(tx_json);
+            });
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/raw/{}", tx.id));
+                then.status(200).body(&tx.data.0);
+            });
+            ids.push(tx.id);
+        }
+        ids.reverse();
+
+        let reassembled = block_on(arweave.reassemble_from_parts(ids.clone())).unwrap();
+        assert_eq!(reassembled, data);
+
+        ids.pop();
+        assert!(matches!(
+            block_on(arweave.reassemble_from_parts(ids)),
+            Err(Error::SplitUploadError(_))
+        ));
+    }
+
+    #[test]
+    fn should_reject_zero_part_size() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+
+        assert!(matches!(
+            block_on(arweave.upload_data_split(vec![0; 100], 0, vec![])),
+            Err(Error::SplitUploadError(_))
+        ));
+    }
 }
@@ -0,0 +1,145 @@
+//! Arweave path manifests, mapping sub-paths to transaction ids so that a folder or
+//! website can be served from a single manifest transaction.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    crypto::base64::Base64,
+    error::Error,
+    transaction::tags::{FromUtf8Strs, Tag},
+};
+
+pub const MANIFEST_TYPE: &str = "arweave/paths";
+pub const MANIFEST_VERSION: &str = "0.1.0";
+pub const MANIFEST_CONTENT_TYPE: &str = "application/x.arweave-manifest+json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ManifestIndex {
+    pub path: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ManifestPath {
+    pub id: Base64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PathManifest {
+    pub manifest: String,
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<ManifestIndex>,
+    pub paths: BTreeMap<String, ManifestPath>,
+}
+
+impl Default for PathManifest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PathManifest {
+    pub fn new() -> PathManifest {
+        PathManifest {
+            manifest: MANIFEST_TYPE.to_string(),
+            version: MANIFEST_VERSION.to_string(),
+            index: None,
+            paths: BTreeMap::new(),
+        }
+    }
+
+    pub fn add_path(mut self, name: &str, tx_id: Base64) -> PathManifest {
+        self.paths
+            .insert(name.to_string(), ManifestPath { id: tx_id });
+        self
+    }
+
+    pub fn set_index(mut self, name: &str) -> PathManifest {
+        self.index = Some(ManifestIndex {
+            path: name.to_string(),
+        });
+        self
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string(self).map_err(|err| Error::ManifestError(err.to_string()))
+    }
+
+    /// Tags the manifest transaction needs for gateways to serve it as a manifest.
+    pub fn tags() -> Result<Vec<Tag<Base64>>, Error> {
+        Ok(vec![Tag::from_utf8_strs(
+            "Content-Type",
+            MANIFEST_CONTENT_TYPE,
+        )?])
+    }
+}
+
+/// Returns the transaction id `path` points to in the manifest, using the manifest index
+/// for an empty path. Returns `None` if the manifest can't be parsed or has no such path.
+pub fn resolve(manifest_bytes: &[u8], path: &str) -> Option<Base64> {
+    let manifest: PathManifest = serde_json::from_slice(manifest_bytes).ok()?;
+    let path = path.trim_start_matches('/');
+    let path = if path.is_empty() {
+        &manifest.index.as_ref()?.path
+    } else {
+        path
+    };
+
+    manifest.paths.get(path).map(|entry| entry.id.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{crypto::base64::Base64, transaction::tags::Tag};
+
+    use super::{resolve, PathManifest};
+
+    #[test]
+    fn test_two_file_manifest_json() {
+        let index_id = Base64::from_str("cG7Hdi_iTQPoEYgQJFqJ8NMpN4KoZ-vH_j7pG4iP7NI").unwrap();
+        let style_id = Base64::from_str("3zFsd7bkCAUtXUKBQ4XiPiQvpLVKfZ6kiLNt2XVSfoU").unwrap();
+
+        let manifest = PathManifest::new()
+            .add_path("style.css", style_id)
+            .add_path("index.html", index_id)
+            .set_index("index.html");
+
+        assert_eq!(
+            manifest.to_json().unwrap(),
+            concat!(
+                r#"{"manifest":"arweave/paths","version":"0.1.0","index":{"path":"index.html"},"#,
+                r#""paths":{"index.html":{"id":"cG7Hdi_iTQPoEYgQJFqJ8NMpN4KoZ-vH_j7pG4iP7NI"},"#,
+                r#""style.css":{"id":"3zFsd7bkCAUtXUKBQ4XiPiQvpLVKfZ6kiLNt2XVSfoU"}}}"#
+            )
+        );
+
+        let tags = PathManifest::tags().unwrap();
+        let tag = Tag::<String>::try_from(&tags[0]).unwrap();
+        assert_eq!(tag.name, "Content-Type");
+        assert_eq!(tag.value, "application/x.arweave-manifest+json");
+    }
+
+    #[test]
+    fn test_resolve() {
+        let index_id = Base64::from_str("cG7Hdi_iTQPoEYgQJFqJ8NMpN4KoZ-vH_j7pG4iP7NI").unwrap();
+        let style_id = Base64::from_str("3zFsd7bkCAUtXUKBQ4XiPiQvpLVKfZ6kiLNt2XVSfoU").unwrap();
+        let manifest = PathManifest::new()
+            .add_path("index.html", index_id.clone())
+            .add_path("css/style.css", style_id.clone())
+            .set_index("index.html")
+            .to_json()
+            .unwrap();
+
+        assert_eq!(
+            resolve(manifest.as_bytes(), "css/style.css"),
+            Some(style_id)
+        );
+        assert_eq!(resolve(manifest.as_bytes(), ""), Some(index_id.clone()));
+        assert_eq!(resolve(manifest.as_bytes(), "/"), Some(index_id));
+        assert_eq!(resolve(manifest.as_bytes(), "missing.html"), None);
+        assert_eq!(resolve(b"not a manifest", "index.html"), None);
+    }
+}
@@ -1,18 +1,17 @@
 use crate::{
-    client::Client,
-    types::{BlockInfo, NetworkInfo},
+    client::{normalize_base_url, Client},
+    consts::{ARWEAVE_NODE_PORT, BLOCK_TXS_CONCURRENCY},
+    error::Error,
+    transaction::Tx,
+    types::{BlockInfo, NetworkInfo, Tx as JsonTx},
 };
+use futures::{stream, Stream, StreamExt};
 use pretend::{
     interceptor::NoopRequestInterceptor, pretend, resolver::UrlResolver, JsonResult, Pretend, Url,
 };
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use thiserror::Error;
 
-#[derive(Serialize, Deserialize, Debug)]
-struct HeightInfo {
-    height: u64,
-}
-
 #[derive(Debug, Error, Deserialize)]
 
 pub enum ResponseError {
@@ -25,23 +24,42 @@ pub enum ResponseError {
 
 #[pretend]
 trait NetworkInfoFetch {
-    #[request(method = "GET", path = "/info")]
+    #[request(method = "GET", path = "info")]
     async fn network_info(&self) -> pretend::Result<JsonResult<NetworkInfo, ResponseError>>;
 
-    #[request(method = "GET", path = "/peers")]
+    #[request(method = "GET", path = "peers")]
     async fn peer_info(&self) -> pretend::Result<JsonResult<Vec<String>, ResponseError>>;
 
-    #[request(method = "GET", path = "/block/hash/{id}")]
+    #[request(method = "GET", path = "block/hash/{id}")]
     async fn block_by_hash(
         &self,
         id: &str,
     ) -> pretend::Result<JsonResult<BlockInfo, ResponseError>>;
 
-    #[request(method = "GET", path = "/block/height/{height}")]
+    #[request(method = "GET", path = "block/height/{height}")]
     async fn block_by_height(
         &self,
         height: u64,
     ) -> pretend::Result<JsonResult<BlockInfo, ResponseError>>;
+
+    #[request(method = "GET", path = "tx/{id}")]
+    async fn tx(&self, id: &str) -> pretend::Result<JsonResult<JsonTx, ResponseError>>;
+}
+
+/// Parses a peer as returned by `/peers` into a node url. Peers without a scheme are
+/// assumed to be `http`, and peers without a scheme or port to use [`ARWEAVE_NODE_PORT`].
+pub fn parse_peer(peer: &str) -> Result<Url, url::ParseError> {
+    let peer = peer.trim();
+    if peer.contains("://") {
+        return Url::parse(peer);
+    }
+
+    let mut url = Url::parse(&format!("http://{}", peer))?;
+    if url.port().is_none() {
+        url.set_port(Some(ARWEAVE_NODE_PORT))
+            .map_err(|_| url::ParseError::InvalidPort)?;
+    }
+    Ok(url)
 }
 
 pub struct NetworkInfoClient(Pretend<Client, UrlResolver, NoopRequestInterceptor>);
@@ -49,7 +67,14 @@ pub struct NetworkInfoClient(Pretend<Client, UrlResolver, NoopRequestInterceptor
 impl NetworkInfoClient {
     pub fn new(url: Url) -> Self {
         let client = Client::default();
-        let pretend = Pretend::for_client(client).with_url(url);
+        let pretend = Pretend::for_client(client).with_url(normalize_base_url(url));
+        Self(pretend)
+    }
+
+    /// Sends the requests with `client` instead of a new one, so that they share its
+    /// connection pool and settings.
+    pub fn with_client(url: Url, client: reqwest::Client) -> Self {
+        let pretend = Pretend::for_client(Client::from(client)).with_url(normalize_base_url(url));
         Self(pretend)
     }
 
@@ -77,6 +102,21 @@ impl NetworkInfoClient {
         }
     }
 
+    /// Fetches the peers of the node as urls, skipping any that can't be parsed.
+    pub async fn peer_urls(&self) -> Result<Vec<Url>, ResponseError> {
+        let peers = self.peer_info().await?;
+        Ok(peers
+            .iter()
+            .filter_map(|peer| match parse_peer(peer) {
+                Ok(url) => Some(url),
+                Err(err) => {
+                    tracing::warn!(%peer, error = %err, "skipping invalid peer");
+                    None
+                }
+            })
+            .collect())
+    }
+
     pub async fn block_by_hash(&self, id: &str) -> Result<BlockInfo, ResponseError> {
         let response = self
             .0
@@ -89,10 +129,10 @@ impl NetworkInfoClient {
         }
     }
 
-    pub async fn block_by_height(&self, id: &str) -> Result<BlockInfo, ResponseError> {
+    pub async fn block_by_height(&self, height: u64) -> Result<BlockInfo, ResponseError> {
         let response = self
             .0
-            .block_by_hash(id)
+            .block_by_height(height)
             .await
             .map_err(|err| ResponseError::InternalError(err.to_string()))?;
         match response {
@@ -100,16 +140,257 @@ impl NetworkInfoClient {
             JsonResult::Err(err) => Err(err),
         }
     }
+
+    pub async fn tx(&self, id: &str) -> Result<Tx, ResponseError> {
+        let response = self
+            .0
+            .tx(id)
+            .await
+            .map_err(|err| ResponseError::InternalError(err.to_string()))?;
+        match response {
+            JsonResult::Ok(tx) => {
+                Tx::try_from(tx).map_err(|err| ResponseError::InternalError(err.to_string()))
+            }
+            JsonResult::Err(err) => Err(err),
+        }
+    }
+
+    /// Fetches the transactions listed in `block`, up to [`BLOCK_TXS_CONCURRENCY`] at a time,
+    /// in block order.
+    pub fn block_transactions<'a>(
+        &'a self,
+        block: &BlockInfo,
+    ) -> impl Stream<Item = Result<Tx, Error>> + 'a {
+        stream::iter(block.txs.clone())
+            .map(move |id| async move {
+                self.tx(&id.to_string())
+                    .await
+                    .map_err(|err| Error::TransactionInfoError(err.to_string()))
+            })
+            .buffered(BLOCK_TXS_CONCURRENCY)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use std::{fs, io::Write, str::FromStr};
 
-    use crate::{consts::ARWEAVE_BASE_URL, crypto::base64::Base64, network::NetworkInfoClient};
+    use crate::{
+        consts::ARWEAVE_BASE_URL,
+        crypto::base64::Base64,
+        error::Error,
+        network::{parse_peer, NetworkInfoClient},
+        test_utils::{block_json, info_json, mock_info, ANCHOR},
+        transaction::Tx,
+        types::BlockInfo,
+    };
+
+    use flate2::{write::GzEncoder, Compression};
+    use futures::StreamExt;
+    use httpmock::{Method::GET, MockServer};
     use pretend::Url;
+    use serde_json::json;
     use tokio_test::block_on;
 
+    #[test]
+    fn test_parse_peer() {
+        assert_eq!(
+            parse_peer("127.0.0.1:1984").unwrap().as_str(),
+            "http://127.0.0.1:1984/"
+        );
+        assert_eq!(
+            parse_peer("node.example").unwrap().as_str(),
+            "http://node.example:1984/"
+        );
+        assert_eq!(
+            parse_peer("https://node.example").unwrap().as_str(),
+            "https://node.example/"
+        );
+        assert!(parse_peer("not a peer").is_err());
+    }
+
+    #[test]
+    fn test_peer_urls_skips_malformed_peers() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/peers");
+            then.status(200).json_body(json!([
+                "127.0.0.1:1984",
+                "not a peer",
+                "http://node.example:8080",
+            ]));
+        });
+
+        let url = Url::parse(&server.url("")).unwrap();
+        let client = NetworkInfoClient::new(url);
+        let peers = block_on(client.peer_urls()).unwrap();
+
+        mock.assert();
+        assert_eq!(
+            peers,
+            vec![
+                Url::parse("http://127.0.0.1:1984").unwrap(),
+                Url::parse("http://node.example:8080").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_gzipped_network_info() {
+        let body = info_json(1_540_000, ANCHOR);
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(body.to_string().as_bytes()).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/info")
+                .header("accept-encoding", "gzip");
+            then.status(200)
+                .header("content-type", "application/json")
+                .header("content-encoding", "gzip")
+                .body(gzipped);
+        });
+
+        let url = Url::parse(&server.url("/")).unwrap();
+        let client = NetworkInfoClient::new(url);
+        let info = block_on(client.network_info()).unwrap();
+
+        mock.assert();
+        assert_eq!(info.height, 1_540_000);
+        assert_eq!(info.current, Base64::from_str(ANCHOR).unwrap());
+    }
+
+    #[test]
+    fn test_network_info_follows_redirects() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(302)
+                .header("location", server.url("/canonical/info"));
+        });
+        let canonical = server.mock(|when, then| {
+            when.method(GET).path("/canonical/info");
+            then.status(200).json_body(info_json(1_540_000, ANCHOR));
+        });
+
+        let url = Url::parse(&server.url("/")).unwrap();
+        let client = NetworkInfoClient::new(url);
+        let info = block_on(client.network_info()).unwrap();
+
+        canonical.assert();
+        assert_eq!(info.height, 1_540_000);
+    }
+
+    #[test]
+    fn test_block_info_serde_roundtrip() {
+        let mut json = block_json(100);
+        json["txs"] = json!([Base64(vec![1; 32])]);
+        json["tags"] = json!([{ "name": "AQ", "value": "Ag" }]);
+        json["cumulative_diff"] = json!("12345");
+        let block: BlockInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(block.diff, "1");
+        assert_eq!(block.hash_list_merkle, None);
+
+        let json = serde_json::to_string(&block).unwrap();
+        let parsed: BlockInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, block);
+    }
+
+    #[test]
+    fn test_block_transactions() {
+        let data = fs::read_to_string("res/sample_tx.json").unwrap();
+        let data_v1 = fs::read_to_string("res/sample_tx_v1.json").unwrap();
+        let tx = Tx::from_str(&data).unwrap();
+        let tx_v1 = Tx::from_str(&data_v1).unwrap();
+
+        let server = MockServer::start();
+        let mut block = block_json(100);
+        block["txs"] = json!([tx.id, tx_v1.id]);
+        server.mock(|when, then| {
+            when.method(GET).path("/block/height/100");
+            then.status(200).json_body(block);
+        });
+        let tx_mocks = [(&tx, &data), (&tx_v1, &data_v1)].map(|(tx, data)| {
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/tx/{}", tx.id));
+                then.status(200).body(data);
+            })
+        });
+
+        let url = Url::parse(&server.url("/")).unwrap();
+        let client = NetworkInfoClient::new(url);
+        let block = block_on(client.block_by_height(100)).unwrap();
+        let txs: Vec<Tx> = block_on(
+            client
+                .block_transactions(&block)
+                .map(|tx| tx.unwrap())
+                .collect(),
+        );
+
+        tx_mocks.iter().for_each(|mock| mock.assert());
+        assert_eq!(txs, vec![tx, tx_v1]);
+    }
+
+    #[test]
+    fn test_block_transactions_malformed_tx() {
+        let data = fs::read_to_string("res/sample_tx.json").unwrap();
+        let tx = Tx::from_str(&data).unwrap();
+        let mut malformed: serde_json::Value = serde_json::from_str(&data).unwrap();
+        malformed["reward"] = json!("not a number");
+
+        let server = MockServer::start();
+        let mut block = block_json(100);
+        block["txs"] = json!([tx.id]);
+        server.mock(|when, then| {
+            when.method(GET).path("/block/height/100");
+            then.status(200).json_body(block);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", tx.id));
+            then.status(200).json_body(malformed);
+        });
+
+        let url = Url::parse(&server.url("/")).unwrap();
+        let client = NetworkInfoClient::new(url);
+        let block = block_on(client.block_by_height(100)).unwrap();
+        let txs: Vec<_> = block_on(client.block_transactions(&block).collect());
+
+        assert_eq!(txs.len(), 1);
+        assert!(matches!(txs[0], Err(Error::TransactionInfoError(_))));
+    }
+
+    #[test]
+    fn test_peer_urls_behind_path_prefix() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/arweave/peers");
+            then.status(200).json_body(json!(["127.0.0.1:1984"]));
+        });
+
+        let url = Url::parse(&server.url("/arweave/")).unwrap();
+        let client = NetworkInfoClient::new(url);
+        let peers = block_on(client.peer_urls()).unwrap();
+
+        mock.assert();
+        assert_eq!(peers, vec![Url::parse("http://127.0.0.1:1984").unwrap()]);
+    }
+
+    #[test]
+    fn test_parse_network_info() {
+        let server = MockServer::start();
+        mock_info(&server, 1_540_000, ANCHOR);
+
+        let url = Url::parse(&server.url("/")).unwrap();
+        let client = NetworkInfoClient::new(url);
+        let info = block_on(client.network_info()).unwrap();
+
+        let height: u64 = info.height;
+        assert_eq!(height, 1_540_000);
+        assert_eq!(info.current, Base64::from_str(ANCHOR).unwrap());
+    }
+
     #[test]
     fn test_network_info() {
         let url = Url::parse(ARWEAVE_BASE_URL).unwrap();
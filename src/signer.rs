@@ -1,21 +1,12 @@
-use std::path::PathBuf;
-
-use data_encoding::BASE64URL;
-use jsonwebkey::JsonWebKey;
-use rand::thread_rng;
-use rsa::{pkcs8::DecodePublicKey, PaddingScheme, PublicKey, RsaPublicKey};
-use sha2::Digest;
+use std::{collections::HashMap, fmt, path::PathBuf};
 
 use crate::{
-    crypto::{
-        base64::Base64,
-        hash::{self, ToItems},
-        verify, Provider,
-    },
+    crypto::{base64::Base64, verify, Provider},
     error::Error,
     transaction::Tx,
 };
 
+#[derive(Clone)]
 pub struct ArweaveSigner {
     crypto: Box<Provider>,
 }
@@ -34,12 +25,9 @@ impl ArweaveSigner {
     }
 
     pub fn sign_transaction(&self, mut transaction: Tx) -> Result<Tx, Error> {
-        let deep_hash_item = transaction.to_deep_hash_item()?;
-        let signature_data = self.crypto.deep_hash(deep_hash_item);
+        let signature_data = transaction.signature_data()?;
         let signature = self.crypto.sign(&signature_data)?;
-        let id = self.crypto.hash_sha256(&signature.0);
-        transaction.signature = signature;
-        transaction.id = Base64(id.to_vec());
+        transaction.apply_signature(signature);
         Ok(transaction)
     }
 
@@ -48,35 +36,7 @@ impl ArweaveSigner {
     }
 
     pub fn verify_transaction(transaction: &Tx) -> Result<(), Error> {
-        if transaction.signature.is_empty() {
-            return Err(Error::UnsignedTransaction);
-        }
-
-        let deep_hash_item = transaction.to_deep_hash_item()?;
-        let message = hash::deep_hash(deep_hash_item);
-        let signature = &transaction.signature;
-
-        let jwt_str = format!(
-            "{{\"kty\":\"RSA\",\"e\":\"AQAB\",\"n\":\"{}\"}}",
-            BASE64URL.encode(&transaction.owner.0)
-        );
-        let jwk: JsonWebKey = jwt_str.parse().unwrap();
-
-        let pub_key = RsaPublicKey::from_public_key_der(jwk.key.to_der().as_slice()).unwrap();
-        let mut hasher = sha2::Sha256::new();
-        hasher.update(message);
-        let hashed = &hasher.finalize();
-
-        let rng = thread_rng();
-        let padding = PaddingScheme::PSS {
-            salt_rng: Box::new(rng),
-            digest: Box::new(sha2::Sha256::new()),
-            salt_len: None,
-        };
-        pub_key
-            .verify(padding, hashed, &signature.0)
-            .map(|_| ())
-            .map_err(|_| Error::InvalidSignature)
+        crate::verify::verify_transaction(transaction)
     }
 
     pub fn wallet_address(&self) -> Base64 {
@@ -96,14 +56,68 @@ impl ArweaveSigner {
     }
 }
 
+/// Only shows the wallet address, never the private key.
+impl fmt::Debug for ArweaveSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArweaveSigner")
+            .field("address", &self.wallet_address().to_string())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Signers for several wallets, looked up by wallet address.
+#[derive(Debug, Default)]
+pub struct WalletStore {
+    signers: HashMap<String, ArweaveSigner>,
+}
+
+impl WalletStore {
+    pub fn new() -> WalletStore {
+        Default::default()
+    }
+
+    pub fn from_keypair_paths<I>(keypair_paths: I) -> Result<WalletStore, Error>
+    where
+        I: IntoIterator<Item = PathBuf>,
+    {
+        let mut store = WalletStore::new();
+        for keypair_path in keypair_paths {
+            store.add_keypair_path(keypair_path)?;
+        }
+        Ok(store)
+    }
+
+    /// Loads the wallet at `keypair_path` and returns its address.
+    pub fn add_keypair_path(&mut self, keypair_path: PathBuf) -> Result<String, Error> {
+        let signer = ArweaveSigner::from_keypair_path(keypair_path)?;
+        Ok(self.add_signer(signer))
+    }
+
+    /// Adds `signer`, replacing any signer for the same wallet, and returns its address.
+    pub fn add_signer(&mut self, signer: ArweaveSigner) -> String {
+        let address = signer.wallet_address().to_string();
+        self.signers.insert(address.clone(), signer);
+        address
+    }
+
+    pub fn signer_for(&self, address: &str) -> Option<&ArweaveSigner> {
+        self.signers.get(address)
+    }
+
+    pub fn addresses(&self) -> impl Iterator<Item = &str> {
+        self.signers.keys().map(String::as_str)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{path::PathBuf, str::FromStr};
 
     use crate::error::Error;
 
-    use super::{ArweaveSigner, Base64};
+    use super::{ArweaveSigner, Base64, WalletStore};
 
+    #[allow(clippy::derivable_impls)]
     impl Default for ArweaveSigner {
         fn default() -> Self {
             Self {
@@ -128,4 +142,45 @@ mod tests {
         let pubk = signer.get_public_key();
         ArweaveSigner::verify(&pubk.0, &message.0, &signature.0)
     }
+
+    #[test]
+    fn test_wallet_store() -> Result<(), Error> {
+        let store = WalletStore::from_keypair_paths([
+            PathBuf::from("res/test_wallet.json"),
+            PathBuf::from("res/test_wallet_2.json"),
+        ])?;
+        assert_eq!(store.addresses().count(), 2);
+
+        for address in [
+            "ggHWyKn0I_CTtsyyt2OR85sPYz9OvKLd9DYIvRQ2ET4",
+            "fZVMa1-QUsQui2r1sEl2qh3DJB6E9SQI80u-wPMaLf0",
+        ] {
+            let signer = store.signer_for(address).unwrap();
+            assert_eq!(signer.wallet_address().to_string(), address);
+        }
+        assert!(store.signer_for("unknown").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug_redacts_private_key() -> Result<(), Error> {
+        let path = PathBuf::from("res/test_wallet.json");
+        let jwk: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path)?).unwrap();
+        let signer = ArweaveSigner::from_keypair_path(path)?;
+        let address = signer.wallet_address().to_string();
+
+        for debug in [
+            format!("{:?}", signer),
+            format!("{:?}", signer.get_provider()),
+            format!("{:?}", signer.get_provider().signer),
+        ] {
+            assert!(debug.contains(&address), "{}", debug);
+            assert!(!debug.contains("priv_key"), "{}", debug);
+            for field in ["n", "d", "p", "q"] {
+                assert!(!debug.contains(jwk[field].as_str().unwrap()), "{}", debug);
+            }
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,149 @@
+//! Fixtures shared by the test modules.
+
+use std::path::PathBuf;
+
+use httpmock::{
+    Method::{GET, POST},
+    Mock, MockServer,
+};
+use serde_json::json;
+
+use crate::{
+    crypto::{
+        base64::Base64,
+        hash::{hash_all_sha256, sha256},
+        merkle::{generate_data_root, resolve_proofs, Helpers, Node},
+    },
+    Arweave,
+};
+
+pub const TEST_WALLET: &str = "res/test_wallet.json";
+pub const REBAR3: &str = "res/rebar3";
+pub const REBAR3_DATA_ROOT: &str = "t-GCOnjPWxdox950JsrFMu3nzOE4RktXpMcIlkqSUTw";
+pub const ANCHOR: &str = "ddvXNxatQmS3LeKi_x1RJn6g9G0esUaTEgT40a6f_WYyawZaSK3w8WC2czAuLgmT";
+
+pub fn test_arweave(server: &MockServer) -> Arweave {
+    let url = url::Url::parse(&server.url("/")).unwrap();
+    Arweave::from_keypair_path(PathBuf::from(TEST_WALLET), url).unwrap()
+}
+
+/// Data root of the data split into `chunks`, along with the chunk response for each of
+/// the chunks, proven against that root.
+pub fn chunk_tree(chunks: &[&[u8]]) -> (Base64, Vec<serde_json::Value>) {
+    let mut min_byte_range = 0;
+    let leaves = chunks
+        .iter()
+        .map(|chunk| {
+            let data_hash = sha256(chunk);
+            let max_byte_range = min_byte_range + chunk.len();
+            let id = hash_all_sha256(vec![&data_hash, &max_byte_range.to_note_vec()]);
+            let leaf = Node {
+                id,
+                data_hash: Some(data_hash),
+                min_byte_range,
+                max_byte_range,
+                left_child: None,
+                right_child: None,
+            };
+            min_byte_range = max_byte_range;
+            leaf
+        })
+        .collect();
+    let root = generate_data_root(leaves).unwrap();
+    let data_root = Base64(root.id.to_vec());
+    let responses = resolve_proofs(root, None)
+        .unwrap()
+        .into_iter()
+        .zip(chunks)
+        .map(|(proof, chunk)| {
+            json!({
+                "chunk": Base64(chunk.to_vec()).to_string(),
+                "data_path": Base64(proof.proof).to_string(),
+                "tx_path": "",
+            })
+        })
+        .collect();
+    (data_root, responses)
+}
+
+/// Serves `data_size` from `/tx/{id}/data_size`.
+pub fn mock_data_size<'a>(server: &'a MockServer, id: &Base64, data_size: u64) -> Mock<'a> {
+    let path = format!("/tx/{}/data_size", id);
+    server.mock(|when, then| {
+        when.method(GET).path(path);
+        then.status(200).body(data_size.to_string());
+    })
+}
+
+/// Serves `data_root` from `/tx/{id}/data_root`.
+pub fn mock_data_root<'a>(server: &'a MockServer, id: &Base64, data_root: &Base64) -> Mock<'a> {
+    let path = format!("/tx/{}/data_root", id);
+    let body = data_root.to_string();
+    server.mock(|when, then| {
+        when.method(GET).path(path);
+        then.status(200).body(body);
+    })
+}
+
+/// `/info` response of a node at `height` whose current block is `current`.
+pub fn info_json(height: u64, current: &str) -> serde_json::Value {
+    json!({
+        "network": "arweave.N.1",
+        "version": 5,
+        "release": 69,
+        "height": height,
+        "current": current,
+        "blocks": height + 1,
+        "peers": 42,
+        "queue_length": 0,
+        "node_state_latency": 1,
+    })
+}
+
+pub fn block_json(height: u64) -> serde_json::Value {
+    json!({
+        "nonce": "",
+        "previous_block": "",
+        "timestamp": 1_700_000_000,
+        "last_retarget": 1_700_000_000,
+        "diff": 1,
+        "height": height,
+        "hash": "",
+        "indep_hash": ANCHOR,
+        "txs": [],
+        "wallet_list": "",
+        "reward_addr": "",
+        "tags": [],
+        "reward_pool": 0,
+        "weave_size": 0,
+        "block_size": 0,
+        "tx_root": "",
+        "tx_tree": [],
+        "poa": { "option": "1", "tx_path": "", "data_path": "", "chunk": "" },
+    })
+}
+
+/// Serves [`ANCHOR`] from `/tx_anchor`.
+pub fn mock_anchor(server: &MockServer) -> Mock<'_> {
+    server.mock(|when, then| {
+        when.method(GET).path("/tx_anchor");
+        then.status(200).body(ANCHOR);
+    })
+}
+
+/// Accepts every transaction posted to `/tx`.
+pub fn mock_post_tx(server: &MockServer) -> Mock<'_> {
+    server.mock(|when, then| {
+        when.method(POST).path("/tx");
+        then.status(200);
+    })
+}
+
+/// Serves [`info_json`] from `/info`.
+pub fn mock_info<'a>(server: &'a MockServer, height: u64, current: &str) -> Mock<'a> {
+    let info = info_json(height, current);
+    server.mock(|when, then| {
+        when.method(GET).path("/info");
+        then.status(200).json_body(info);
+    })
+}
@@ -1,19 +1,25 @@
+use futures_timer::Delay;
 use reqwest::{
     header::{ACCEPT, CONTENT_TYPE},
     StatusCode,
 };
 use serde_json::json;
-use std::{str::FromStr, thread::sleep, time::Duration};
+use std::{str::FromStr, time::Duration};
 
 use crate::{
-    consts::{ARWEAVE_BASE_URL, CHUNKS_RETRIES, CHUNKS_RETRY_SLEEP},
+    client::{http_client, normalize_base_url},
+    consts::{ARWEAVE_BASE_URL, CHUNKS_RETRIES, CHUNKS_RETRY_SLEEP, MAX_TX_DATA},
     crypto::base64::Base64,
     error::Error,
-    types::TxStatus,
+    types::{ConfirmationState, TxField, TxStatus},
 };
 
 use super::Tx;
 
+/// Body returned by nodes rejecting a transaction whose `last_tx` is no longer valid.
+const INVALID_ANCHOR_MSG: &str = "Invalid anchor";
+
+#[derive(Clone)]
 pub struct TxClient {
     client: reqwest::Client,
     base_url: url::Url,
@@ -22,7 +28,7 @@ pub struct TxClient {
 impl Default for TxClient {
     fn default() -> Self {
         Self {
-            client: reqwest::Client::new(),
+            client: http_client(),
             base_url: url::Url::from_str(ARWEAVE_BASE_URL).unwrap(),
         }
     }
@@ -30,7 +36,10 @@ impl Default for TxClient {
 
 impl TxClient {
     pub fn new(client: reqwest::Client, base_url: url::Url) -> Result<Self, Error> {
-        Ok(Self { client, base_url })
+        Ok(Self {
+            client,
+            base_url: normalize_base_url(base_url),
+        })
     }
 
     pub async fn post_transaction(&self, signed_transaction: &Tx) -> Result<(Base64, u64), Error> {
@@ -39,11 +48,10 @@ impl TxClient {
         }
 
         let mut retries = 0;
-        let mut status = reqwest::StatusCode::NOT_FOUND;
-        let url = self.base_url.join("tx").map_err(Error::UrlParseError)?;
+        let mut last_error = Error::StatusCodeNotOk;
+        let url = self.base_url.join("tx")?;
 
-        dbg!(json!(signed_transaction));
-        while (retries < CHUNKS_RETRIES) & (status != reqwest::StatusCode::OK) {
+        while retries < CHUNKS_RETRIES {
             let res = self
                 .client
                 .post(url.clone())
@@ -51,69 +59,93 @@ impl TxClient {
                 .header(&ACCEPT, "application/json")
                 .header(&CONTENT_TYPE, "application/json")
                 .send()
-                .await
-                .map_err(Error::ReqwestError)?;
-            status = res.status();
-            dbg!(status);
-            if status == reqwest::StatusCode::OK {
-                return Ok((signed_transaction.id.clone(), signed_transaction.reward));
+                .await;
+            match res {
+                Ok(res) => {
+                    let status = res.status();
+                    tracing::debug!(id = %signed_transaction.id, %status, retries, "posted transaction");
+                    if status == StatusCode::OK {
+                        return Ok((signed_transaction.id.clone(), signed_transaction.reward));
+                    }
+                    // Client errors won't succeed on retry, except for rate limiting.
+                    if status.is_client_error() && status != StatusCode::TOO_MANY_REQUESTS {
+                        if status == StatusCode::PAYLOAD_TOO_LARGE {
+                            return Err(Error::DataTooLarge {
+                                size: signed_transaction.data_size,
+                                limit: MAX_TX_DATA,
+                            });
+                        }
+                        let body = res.text().await?;
+                        if body.contains(INVALID_ANCHOR_MSG) {
+                            return Err(Error::StaleAnchor);
+                        }
+                        return Err(Error::TransactionRejected(status.as_u16(), body));
+                    }
+                    last_error = Error::StatusCodeNotOk;
+                }
+                Err(err) => last_error = Error::ReqwestError(err),
             }
-            sleep(Duration::from_secs(CHUNKS_RETRY_SLEEP));
             retries += 1;
+            if retries < CHUNKS_RETRIES {
+                Delay::new(Duration::from_secs(CHUNKS_RETRY_SLEEP)).await;
+            }
         }
 
-        Err(Error::StatusCodeNotOk)
+        Err(last_error)
     }
 
     pub async fn get_last_tx(&self) -> Result<Base64, Error> {
         let resp = self
             .client
-            .get(
-                self.base_url
-                    .join("tx_anchor")
-                    .map_err(Error::UrlParseError)?,
-            )
+            .get(self.base_url.join("tx_anchor")?)
             .send()
-            .await
-            .map_err(Error::ReqwestError)?;
-        let last_tx_str = resp.text().await.unwrap();
-        Base64::from_str(&last_tx_str).map_err(Error::Base64DecodeError)
+            .await?;
+        let last_tx_str = resp.text().await?;
+        Ok(Base64::from_str(&last_tx_str)?)
     }
 
     pub async fn get_fee(&self, target: Base64, data: Vec<u8>) -> Result<u64, Error> {
-        let url = self
-            .base_url
-            .join(&format!("price/{}/{}", data.len(), target))
-            .map_err(Error::UrlParseError)?;
-        let winstons_per_bytes = reqwest::get(url)
-            .await
-            .map_err(|e| Error::GetPriceError(e.to_string()))?
-            .json::<u64>()
-            .await
-            .map_err(Error::ReqwestError)?;
+        self.get_fee_by_size(target, data.len() as u64).await
+    }
+
+    pub async fn get_fee_by_size(&self, target: Base64, byte_size: u64) -> Result<u64, Error> {
+        let path = if target.is_empty() {
+            format!("price/{}", byte_size)
+        } else {
+            format!("price/{}/{}", byte_size, target)
+        };
+        let url = self.base_url.join(&path)?;
+        // Unreachable gateways keep the reqwest error so read failover can pick them up.
+        let res = self.client.get(url).send().await.map_err(|e| {
+            if e.is_connect() || e.is_timeout() {
+                Error::ReqwestError(e)
+            } else {
+                Error::GetPriceError(e.to_string())
+            }
+        })?;
+        if res.status().is_server_error() {
+            return Err(Error::ServerError(res.status().as_u16()));
+        }
 
-        Ok(winstons_per_bytes)
+        res.json::<u64>().await.map_err(Error::ReqwestError)
     }
 
     pub async fn get_tx(&self, id: Base64) -> Result<(StatusCode, Option<Tx>), Error> {
         let res = self
             .client
-            .get(
-                self.base_url
-                    .join(&format!("tx/{}", id))
-                    .map_err(Error::UrlParseError)?,
-            )
+            .get(self.base_url.join(&format!("tx/{}", id))?)
             .send()
-            .await
-            .map_err(Error::ReqwestError)?;
+            .await?;
 
         if res.status() == StatusCode::OK {
-            let text = res.text().await.map_err(Error::ReqwestError)?;
+            let text = res.text().await?;
             let tx = Tx::from_str(&text)?;
             return Ok((StatusCode::OK, Some(tx)));
         } else if res.status() == StatusCode::ACCEPTED {
             //Tx is pending
             return Ok((StatusCode::ACCEPTED, None));
+        } else if res.status().is_server_error() {
+            return Err(Error::ServerError(res.status().as_u16()));
         }
 
         Err(Error::TransactionInfoError(res.status().to_string()))
@@ -122,14 +154,9 @@ impl TxClient {
     pub async fn get_tx_status(&self, id: Base64) -> Result<(StatusCode, Option<TxStatus>), Error> {
         let res = self
             .client
-            .get(
-                self.base_url
-                    .join(&format!("tx/{}/status", id))
-                    .map_err(Error::UrlParseError)?,
-            )
+            .get(self.base_url.join(&format!("tx/{}/status", id))?)
             .send()
-            .await
-            .map_err(Error::ReqwestError)?;
+            .await?;
 
         if res.status() == StatusCode::OK {
             let status = res
@@ -140,8 +167,306 @@ impl TxClient {
             Ok((StatusCode::OK, Some(status)))
         } else if res.status() == StatusCode::ACCEPTED {
             Ok((StatusCode::ACCEPTED, None))
+        } else if res.status().is_server_error() {
+            Err(Error::ServerError(res.status().as_u16()))
         } else {
             Err(Error::TransactionInfoError(res.status().to_string()))
         }
     }
+
+    /// Returns the balance of the wallet at `address` in winstons.
+    pub async fn get_balance(&self, address: &str) -> Result<String, Error> {
+        let res = self
+            .client
+            .get(self.base_url.join(&format!("wallet/{}/balance", address))?)
+            .send()
+            .await?;
+
+        match res.status() {
+            StatusCode::OK => Ok(res.text().await?),
+            status if status.is_server_error() => Err(Error::ServerError(status.as_u16())),
+            status => Err(Error::WalletError(status.to_string())),
+        }
+    }
+
+    /// Returns a single field of the transaction, as served by `/tx/{id}/{field}`.
+    pub async fn get_tx_field(&self, id: Base64, field: &str) -> Result<String, Error> {
+        let res = self
+            .client
+            .get(self.base_url.join(&format!("tx/{}/{}", id, field))?)
+            .send()
+            .await?;
+
+        match res.status() {
+            StatusCode::OK => Ok(res.text().await?),
+            status if status.is_server_error() => Err(Error::ServerError(status.as_u16())),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    /// Same as [`TxClient::get_tx_field`], with the field picked from the ones gateways serve.
+    pub async fn get_known_tx_field(&self, id: Base64, field: TxField) -> Result<String, Error> {
+        self.get_tx_field(id, field.as_str()).await
+    }
+
+    /// Returns the ids of the transactions waiting in the mempool, as served by `/tx/pending`.
+    pub async fn get_pending(&self) -> Result<Vec<Base64>, Error> {
+        let res = self
+            .client
+            .get(self.base_url.join("tx/pending")?)
+            .send()
+            .await?;
+
+        match res.status() {
+            StatusCode::OK => Ok(res.json().await?),
+            status if status.is_server_error() => Err(Error::ServerError(status.as_u16())),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    /// Returns the id of the most recent transaction tagged `name: value`, using the gateway
+    /// GraphQL endpoint. With `owner` set, only transactions of that wallet address are
+    /// searched.
+    pub async fn find_tx_by_tag(
+        &self,
+        name: &str,
+        value: &str,
+        owner: Option<&str>,
+    ) -> Result<Option<Base64>, Error> {
+        let query = r#"query($name: String!, $value: String!, $owners: [String!]) {
+            transactions(first: 1, owners: $owners, tags: [{ name: $name, values: [$value] }]) {
+                edges { node { id } }
+            }
+        }"#;
+        let res = self
+            .client
+            .post(self.base_url.join("graphql")?)
+            .json(&json!({
+                "query": query,
+                "variables": {
+                    "name": name,
+                    "value": value,
+                    "owners": owner.map(|owner| vec![owner]),
+                },
+            }))
+            .send()
+            .await?;
+
+        match res.status() {
+            StatusCode::OK => {
+                let body: serde_json::Value = res.json().await?;
+                body.pointer("/data/transactions/edges/0/node/id")
+                    .and_then(|id| id.as_str())
+                    .map(Base64::from_str)
+                    .transpose()
+                    .map_err(Error::from)
+            }
+            status if status.is_server_error() => Err(Error::ServerError(status.as_u16())),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    pub async fn get_confirmation_state(&self, id: Base64) -> Result<ConfirmationState, Error> {
+        let res = self
+            .client
+            .get(self.base_url.join(&format!("tx/{}/status", id))?)
+            .send()
+            .await?;
+
+        match res.status() {
+            StatusCode::OK => res
+                .json::<TxStatus>()
+                .await
+                .map(ConfirmationState::Confirmed)
+                .map_err(|err| Error::TransactionInfoError(err.to_string())),
+            StatusCode::ACCEPTED => Ok(ConfirmationState::Pending),
+            StatusCode::NOT_FOUND => Ok(ConfirmationState::NotFound),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use tokio_test::block_on;
+
+    use crate::{
+        consts::MAX_TX_DATA, crypto::base64::Base64, error::Error, transaction::Tx, types::TxField,
+    };
+
+    use super::TxClient;
+
+    fn test_client(server: &MockServer) -> TxClient {
+        let url = url::Url::parse(&server.url("/")).unwrap();
+        TxClient::new(reqwest::Client::new(), url).unwrap()
+    }
+
+    #[test]
+    fn test_get_fee_data_only() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/price/4");
+            then.status(200).body("1234");
+        });
+
+        let client = test_client(&server);
+        let fee = block_on(client.get_fee(Base64::empty(), b"data".to_vec())).unwrap();
+
+        mock.assert();
+        assert_eq!(fee, 1234);
+    }
+
+    #[test]
+    fn test_get_fee_with_target() {
+        let target = "PAgdonEn9f5xd-UbYdCX40Sj28eltQVnxz6bbUijeVY";
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/price/0/{}", target));
+            then.status(200).body("5678");
+        });
+
+        let client = test_client(&server);
+        let fee = block_on(client.get_fee(Base64::from_str(target).unwrap(), vec![])).unwrap();
+
+        mock.assert();
+        assert_eq!(fee, 5678);
+    }
+
+    #[test]
+    fn test_get_fee_invalid_body() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/price/4");
+            then.status(200).body("not a number");
+        });
+
+        let client = test_client(&server);
+        let res = block_on(client.get_fee(Base64::empty(), b"data".to_vec()));
+
+        assert!(matches!(res, Err(Error::ReqwestError(_))));
+    }
+
+    #[test]
+    fn test_post_transaction_fails_fast_on_client_error() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(400).body("Invalid JSON.");
+        });
+
+        let client = test_client(&server);
+        let tx = Tx {
+            id: Base64(vec![1; 32]),
+            ..Default::default()
+        };
+        let res = block_on(client.post_transaction(&tx));
+
+        mock.assert_hits(1);
+        assert!(matches!(
+            res,
+            Err(Error::TransactionRejected(400, body)) if body == "Invalid JSON."
+        ));
+    }
+
+    #[test]
+    fn test_post_transaction_data_too_large() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(413).body("Payload too large");
+        });
+
+        let client = test_client(&server);
+        let tx = Tx {
+            id: Base64(vec![1; 32]),
+            data: Base64(vec![0; 1024]),
+            data_size: 1024,
+            ..Default::default()
+        };
+        let res = block_on(client.post_transaction(&tx));
+
+        mock.assert_hits(1);
+        assert!(matches!(
+            res,
+            Err(Error::DataTooLarge {
+                size: 1024,
+                limit: MAX_TX_DATA
+            })
+        ));
+    }
+
+    #[test]
+    fn test_post_transaction_rejected_mentioning_too_large() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(400).body("Tag value too large.");
+        });
+
+        let client = test_client(&server);
+        let tx = Tx {
+            id: Base64(vec![1; 32]),
+            ..Default::default()
+        };
+        let res = block_on(client.post_transaction(&tx));
+
+        assert!(matches!(
+            res,
+            Err(Error::TransactionRejected(400, body)) if body == "Tag value too large."
+        ));
+    }
+
+    #[test]
+    fn test_get_tx_field_owner() {
+        let id = Base64(vec![1; 32]);
+        let owner = Base64(vec![2; 512]);
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/owner", id));
+            then.status(200).body(owner.to_string());
+        });
+
+        let client = test_client(&server);
+        let res = block_on(client.get_tx_field(id.clone(), "owner")).unwrap();
+        assert_eq!(res, owner.to_string());
+
+        let res = block_on(client.get_known_tx_field(id, TxField::Owner)).unwrap();
+        assert_eq!(res, owner.to_string());
+        mock.assert_hits(2);
+    }
+
+    #[test]
+    fn test_requests_keep_base_path_prefix() {
+        let server = MockServer::start();
+        let anchor_mock = server.mock(|when, then| {
+            when.method(GET).path("/arweave/tx_anchor");
+            then.status(200)
+                .body("V6YjG8G3he0JIIwRtzTccX39rS0jH-jOqUJy6rxrVAHY0RT0AVhG8K22wCDxy1A0");
+        });
+        let tx_mock = server.mock(|when, then| {
+            when.method(POST).path("/arweave/tx");
+            then.status(200);
+        });
+
+        // with and without a trailing slash
+        for base_path in ["/arweave/", "/arweave"] {
+            let url = url::Url::parse(&server.url(base_path)).unwrap();
+            let client = TxClient::new(reqwest::Client::new(), url).unwrap();
+            let tx = Tx {
+                id: Base64(vec![1; 32]),
+                ..Default::default()
+            };
+            block_on(client.get_last_tx()).unwrap();
+            block_on(client.post_transaction(&tx)).unwrap();
+        }
+
+        anchor_mock.assert_hits(2);
+        tx_mock.assert_hits(2);
+    }
 }
@@ -1,16 +1,21 @@
+use std::collections::HashMap;
+
 use serde::Deserialize;
 
 use crate::{
     consts::VERSION,
     crypto::{base64::Base64, Provider},
     crypto::{
-        hash::{DeepHashItem, ToItems},
-        merkle::{generate_data_root, generate_leaves, resolve_proofs, Node, Proof},
+        hash::{deep_hash, sha256, DeepHashItem, ToItems},
+        merkle::{
+            generate_data_root, generate_leaves, resolve_proofs, validate_chunk, Node, Proof,
+        },
+        utils::public_jwk_string,
     },
-    currency::Currency,
+    currency::{Currency, MAX_SUPPLY_WINSTONS},
     error::Error,
     transaction::tags::Tag,
-    types::Chunk,
+    types::{Chunk, VerificationCheck},
 };
 
 use self::tags::FromUtf8Strs;
@@ -19,7 +24,7 @@ pub mod client;
 pub mod parser;
 pub mod tags;
 
-#[derive(Deserialize, Debug, Default, PartialEq)]
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
 pub struct Tx {
     /* Fields required for signing */
     pub format: u8,
@@ -40,53 +45,117 @@ pub struct Tx {
     pub proofs: Vec<Proof>,
 }
 
-impl<'a> ToItems<'a, Tx> for Tx {
+/// Fields a transaction is signed over, in the form they are signed in. Built by [`Tx`]
+/// and by the verification of `/tx` JSON, whose numeric fields are signed as received.
+pub(crate) struct SignatureFields<'a> {
+    pub format: u8,
+    pub owner: &'a [u8],
+    pub target: &'a [u8],
+    pub data: &'a [u8],
+    pub quantity: String,
+    pub reward: String,
+    pub last_tx: &'a [u8],
+    pub tags: Vec<Tag<Base64>>,
+    pub data_size: String,
+    pub data_root: &'a [u8],
+}
+
+impl<'a> ToItems<'a, SignatureFields<'a>> for SignatureFields<'a> {
     fn to_deep_hash_item(&'a self) -> Result<DeepHashItem, Error> {
         match &self.format {
-            1 => {
-                let quantity = Base64::from_utf8_str(&self.quantity.to_string()).unwrap();
-                let reward = Base64::from_utf8_str(&self.reward.to_string()).unwrap();
-                let mut children: Vec<DeepHashItem> = vec![
-                    &self.owner,
-                    &self.target,
-                    &self.data,
-                    &quantity,
-                    &reward,
-                    &self.last_tx,
-                ]
-                .into_iter()
-                .map(|op| DeepHashItem::from_item(&op.0))
-                .collect();
-                children.push(self.tags.to_deep_hash_item()?);
-
-                Ok(DeepHashItem::from_children(children))
-            }
             2 => {
                 let mut children: Vec<DeepHashItem> = vec![
                     self.format.to_string().as_bytes(),
-                    &self.owner.0,
-                    &self.target.0,
-                    self.quantity.to_string().as_bytes(),
-                    self.reward.to_string().as_bytes(),
-                    &self.last_tx.0,
+                    self.owner,
+                    self.target,
+                    self.quantity.as_bytes(),
+                    self.reward.as_bytes(),
+                    self.last_tx,
                 ]
                 .into_iter()
                 .map(DeepHashItem::from_item)
                 .collect();
-                children.push(self.tags.to_deep_hash_item().unwrap());
-                children.push(DeepHashItem::from_item(
-                    self.data_size.to_string().as_bytes(),
-                ));
-                children.push(DeepHashItem::from_item(&self.data_root.0));
+                children.push(self.tags.to_deep_hash_item()?);
+                children.push(DeepHashItem::from_item(self.data_size.as_bytes()));
+                children.push(DeepHashItem::from_item(self.data_root));
 
                 Ok(DeepHashItem::from_children(children))
             }
-            _ => unreachable!(),
+            _ => Err(Error::InvalidValueForTx),
         }
     }
 }
 
+impl SignatureFields<'_> {
+    /// Format 1 transactions sign the concatenation of their fields, format 2 transactions
+    /// sign the deep hash of them.
+    pub fn signature_data(&self) -> Result<Vec<u8>, Error> {
+        match self.format {
+            1 => {
+                let mut data = [
+                    self.owner,
+                    self.target,
+                    self.data,
+                    self.quantity.as_bytes(),
+                    self.reward.as_bytes(),
+                    self.last_tx,
+                ]
+                .concat();
+                for tag in &self.tags {
+                    data.extend(&tag.name.0);
+                    data.extend(&tag.value.0);
+                }
+                Ok(data)
+            }
+            _ => Ok(deep_hash(self.to_deep_hash_item()?).to_vec()),
+        }
+    }
+}
+
+impl<'a> ToItems<'a, Tx> for Tx {
+    fn to_deep_hash_item(&'a self) -> Result<DeepHashItem, Error> {
+        self.signature_fields().to_deep_hash_item()
+    }
+}
+
 impl Tx {
+    /// Returns the message signed for the transaction. Format 1 transactions sign the
+    /// concatenation of their fields, format 2 transactions sign the deep hash of them.
+    pub fn signature_data(&self) -> Result<Vec<u8>, Error> {
+        self.signature_fields().signature_data()
+    }
+
+    fn signature_fields(&self) -> SignatureFields<'_> {
+        SignatureFields {
+            format: self.format,
+            owner: &self.owner.0,
+            target: &self.target.0,
+            data: &self.data.0,
+            quantity: self.quantity.to_string(),
+            reward: self.reward.to_string(),
+            last_tx: &self.last_tx.0,
+            tags: self.tags.clone(),
+            data_size: self.data_size.to_string(),
+            data_root: &self.data_root.0,
+        }
+    }
+
+    /// Returns the deep hash an external signer has to sign for a format 2 transaction. The
+    /// resulting signature can then be set with [`Tx::apply_signature`].
+    ///
+    /// Format 1 transactions sign the concatenation of their fields rather than a deep hash,
+    /// so they are rejected with [`Error::InvalidValueForTx`]. Their message is returned by
+    /// [`Tx::signature_data`].
+    pub fn signing_message(&self) -> Result<[u8; 48], Error> {
+        Ok(deep_hash(self.to_deep_hash_item()?))
+    }
+
+    /// Sets the signature and the id derived from it.
+    pub fn apply_signature(&mut self, signature: Base64) {
+        self.id = Base64(sha256(&signature.0).to_vec());
+        self.signature = signature;
+    }
+
     fn base_tag() -> Tag<Base64> {
         Tag::<Base64>::from_utf8_strs("User-Agent", &format!("arweave-rs/{}", VERSION)).unwrap()
     }
@@ -141,10 +210,12 @@ impl Tx {
         other_tags: Vec<Tag<Base64>>,
         auto_content_tag: bool,
     ) -> Result<Self, Error> {
-        if quantity.lt(&0) {
+        if quantity > MAX_SUPPLY_WINSTONS {
             return Err(Error::InvalidValueForTx);
         }
 
+        validate_anchor(&last_tx)?;
+
         let mut transaction = Tx::generate_merkle(data).unwrap();
         transaction.owner = crypto.keypair_modulus();
 
@@ -195,16 +266,495 @@ impl Tx {
         })
     }
 
-    pub fn get_chunk(&self, idx: usize) -> Result<Chunk, Error> {
-        Ok(Chunk {
+    /// Returns an unsigned copy of the transaction with new `tags` and `fee`, keeping the
+    /// data and its merkle tree so it can be re-signed without re-chunking the data.
+    pub fn with_updated_metadata(&self, tags: Vec<Tag<Base64>>, fee: u64) -> Self {
+        Self {
+            format: self.format,
+            id: Base64::default(),
+            last_tx: self.last_tx.clone(),
+            owner: self.owner.clone(),
+            tags,
+            target: self.target.clone(),
+            quantity: self.quantity,
             data_root: self.data_root.clone(),
+            data: self.data.clone(),
             data_size: self.data_size,
-            data_path: Base64(self.proofs[idx].proof.clone()),
-            offset: self.proofs[idx].offset,
-            chunk: Base64(
-                self.data.0[self.chunks[idx].min_byte_range..self.chunks[idx].max_byte_range]
-                    .to_vec(),
-            ),
-        })
+            reward: fee,
+            signature: Base64::default(),
+            chunks: self.chunks.clone(),
+            proofs: self.proofs.clone(),
+        }
+    }
+
+    pub fn get_chunk(&self, idx: usize) -> Result<Chunk, Error> {
+        let mut chunk = Chunk::build(
+            self.data_root.clone(),
+            &self.data.0,
+            self.chunks[idx].min_byte_range,
+            self.chunks[idx].max_byte_range,
+            &self.proofs[idx],
+        );
+        chunk.data_size = self.data_size;
+        Ok(chunk)
+    }
+}
+
+impl Tx {
+    /// Sorts tags by name, then value, so that the same logical set of tags always
+    /// produces the same signing message. Arweave preserves tag order, so sorting
+    /// changes the transaction id compared to the original order.
+    pub fn sort_tags(&mut self) {
+        self.tags
+            .sort_by(|a, b| (&a.name.0, &a.value.0).cmp(&(&b.name.0, &b.value.0)));
+    }
+
+    /// Returns the owner public key as a JWK string, e.g. for use with external tools.
+    pub fn owner_jwk_string(&self) -> String {
+        public_jwk_string(&self.owner.0)
+    }
+
+    /// Re-chunks the data and checks that it produces `data_root`, and that every chunk and
+    /// proof that will be uploaded validates against it.
+    pub fn validate_data_root(&self) -> Result<(), Error> {
+        if self.data.is_empty() {
+            return match self.data_root.is_empty() {
+                true => Ok(()),
+                false => Err(Error::TransactionVerificationFailed(
+                    VerificationCheck::DataRoot,
+                )),
+            };
+        }
+
+        let root = generate_data_root(generate_leaves(self.data.0.clone())?)?;
+        if root.id[..] != self.data_root.0[..] {
+            return Err(Error::TransactionVerificationFailed(
+                VerificationCheck::DataRoot,
+            ));
+        }
+
+        if self.chunks.is_empty() || self.chunks.len() != self.proofs.len() {
+            return Err(Error::InvalidProof);
+        }
+        for (chunk, proof) in self.chunks.iter().zip(&self.proofs) {
+            let data = self
+                .data
+                .0
+                .get(chunk.min_byte_range..chunk.max_byte_range)
+                .ok_or(Error::InvalidProof)?;
+            if chunk.data_hash != Some(sha256(data)) {
+                return Err(Error::InvalidProof);
+            }
+            validate_chunk(root.id, chunk.clone(), proof.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Iterates over all chunks of the transaction data, in order.
+    pub fn chunks_iter(&self) -> impl Iterator<Item = Result<Chunk, Error>> + '_ {
+        (0..self.chunks.len()).map(move |idx| self.get_chunk(idx))
+    }
+
+    pub fn tags(&self) -> &[Tag<Base64>] {
+        &self.tags
+    }
+
+    /// Appends `tag`. Fails once the transaction is signed, as the tags are part of the
+    /// signature.
+    ///
+    /// The check is only advisory: `tags` and `signature` are public fields, so code writing
+    /// them directly bypasses it. A transaction whose tags change after signing fails
+    /// verification, see [`crate::Arweave::verify_transaction`].
+    pub fn add_tag(&mut self, tag: Tag<Base64>) -> Result<(), Error> {
+        self.ensure_unsigned()?;
+        self.tags.push(tag);
+        Ok(())
+    }
+
+    /// Removes every tag named `name`, returning how many were removed. Fails once the
+    /// transaction is signed, with the same caveat as [`Tx::add_tag`].
+    pub fn remove_tag(&mut self, name: &str) -> Result<usize, Error> {
+        self.ensure_unsigned()?;
+        let len = self.tags.len();
+        self.tags.retain(|tag| tag.name.0 != name.as_bytes());
+        Ok(len - self.tags.len())
+    }
+
+    fn ensure_unsigned(&self) -> Result<(), Error> {
+        if !self.signature.is_empty() {
+            return Err(Error::AlreadySigned);
+        }
+        Ok(())
+    }
+
+    /// Returns the utf-8 decoded value of the first tag named `name`.
+    pub fn get_tag(&self, name: &str) -> Option<String> {
+        self.tags
+            .iter()
+            .filter_map(|tag| Tag::<String>::try_from(tag).ok())
+            .find(|tag| tag.name == name)
+            .map(|tag| tag.value)
+    }
+
+    /// Returns the utf-8 decoded values of every tag named `name`, in tag order.
+    pub fn get_all_tags(&self, name: &str) -> Vec<String> {
+        self.tags
+            .iter()
+            .filter_map(|tag| Tag::<String>::try_from(tag).ok())
+            .filter(|tag| tag.name == name)
+            .map(|tag| tag.value)
+            .collect()
+    }
+
+    /// Returns the utf-8 decoded tags as a map. For repeated names the first value is kept.
+    pub fn tags_map(&self) -> HashMap<String, String> {
+        let mut map = HashMap::new();
+        for tag in self
+            .tags
+            .iter()
+            .filter_map(|tag| Tag::<String>::try_from(tag).ok())
+        {
+            map.entry(tag.name).or_insert(tag.value);
+        }
+        map
+    }
+}
+
+/// Anchors are either the id of a recent transaction (32 bytes) or the hash of a recent
+/// block (48 bytes), as returned by `/tx_anchor`.
+pub fn validate_anchor(anchor: &Base64) -> Result<(), Error> {
+    match anchor.0.len() {
+        32 | 48 => Ok(()),
+        len => Err(Error::InvalidAnchor(len)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, str::FromStr};
+
+    use jsonwebkey::JsonWebKey;
+
+    use crate::{
+        crypto::{
+            base64::Base64,
+            hash::{deep_hash, ToItems},
+            Provider,
+        },
+        currency::MAX_SUPPLY_WINSTONS,
+        error::Error,
+        transaction::tags::{FromUtf8Strs, Tag},
+    };
+
+    use super::Tx;
+
+    fn tagged_tx() -> Tx {
+        let tags = vec![
+            ("Content-Type", "text/plain"),
+            ("App-Name", "arweave-rs"),
+            ("Key", "first"),
+            ("Key", "second"),
+        ]
+        .into_iter()
+        .map(|(name, value)| Tag::from_utf8_strs(name, value).unwrap())
+        .collect();
+
+        Tx {
+            tags,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_get_tag() {
+        let tx = tagged_tx();
+        assert_eq!(tx.get_tag("Content-Type"), Some("text/plain".to_string()));
+        assert_eq!(tx.get_tag("Key"), Some("first".to_string()));
+        assert_eq!(tx.get_tag("Missing"), None);
+    }
+
+    #[test]
+    fn test_get_all_tags() {
+        let tx = tagged_tx();
+        assert_eq!(
+            tx.get_all_tags("Key"),
+            vec!["first".to_string(), "second".to_string()]
+        );
+        assert_eq!(tx.get_all_tags("App-Name"), vec!["arweave-rs".to_string()]);
+        assert!(tx.get_all_tags("Missing").is_empty());
+    }
+
+    #[test]
+    fn test_tags_map() {
+        let map = tagged_tx().tags_map();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map["App-Name"], "arweave-rs");
+        assert_eq!(map["Key"], "first");
+    }
+
+    #[test]
+    fn test_add_and_remove_tags() {
+        let mut tx = tagged_tx();
+        tx.add_tag(Tag::from_utf8_strs("Late", "tag").unwrap())
+            .unwrap();
+        assert_eq!(tx.tags().len(), 5);
+        assert_eq!(tx.get_tag("Late"), Some("tag".to_string()));
+
+        assert_eq!(tx.remove_tag("Key").unwrap(), 2);
+        assert_eq!(tx.remove_tag("Missing").unwrap(), 0);
+        assert_eq!(tx.tags().len(), 3);
+        assert!(tx.get_all_tags("Key").is_empty());
+    }
+
+    #[test]
+    fn test_reject_tag_changes_once_signed() {
+        let provider = Provider::default();
+        let mut tx = Tx {
+            format: 2,
+            owner: provider.keypair_modulus(),
+            ..tagged_tx()
+        };
+        tx.signature = provider.sign(&tx.signature_data().unwrap()).unwrap();
+
+        assert!(matches!(
+            tx.add_tag(Tag::from_utf8_strs("Late", "tag").unwrap()),
+            Err(Error::AlreadySigned)
+        ));
+        assert!(matches!(tx.remove_tag("Key"), Err(Error::AlreadySigned)));
+        assert_eq!(tx.tags().len(), 4);
+    }
+
+    #[test]
+    fn test_chunks_iter() {
+        let data = fs::read("res/rebar3").unwrap();
+        let tx = Tx::generate_merkle(data.clone()).unwrap();
+        assert!(tx.chunks.len() > 1);
+
+        let chunks: Vec<_> = tx.chunks_iter().map(|chunk| chunk.unwrap()).collect();
+        assert_eq!(chunks.len(), tx.chunks.len());
+
+        let joined: Vec<u8> = chunks.into_iter().flat_map(|chunk| chunk.chunk.0).collect();
+        assert_eq!(joined, data);
+    }
+
+    #[test]
+    fn test_get_chunk_fields() {
+        let data = fs::read("res/rebar3").unwrap();
+        let tx = Tx::generate_merkle(data.clone()).unwrap();
+
+        let first = tx.get_chunk(0).unwrap();
+        assert_eq!(first.data_root, tx.data_root);
+        assert_eq!(first.data_size, data.len() as u64);
+        assert_eq!(first.size(), tx.chunks[0].max_byte_range);
+
+        let sizes: usize = tx.chunks_iter().map(|chunk| chunk.unwrap().size()).sum();
+        assert_eq!(sizes, data.len());
+    }
+
+    #[test]
+    fn test_sort_tags() {
+        let tags = |pairs: &[(&str, &str)]| -> Vec<Tag<_>> {
+            pairs
+                .iter()
+                .map(|(name, value)| Tag::from_utf8_strs(name, value).unwrap())
+                .collect()
+        };
+        let mut tx_a = Tx {
+            format: 2,
+            tags: tags(&[("b", "1"), ("a", "2"), ("a", "1")]),
+            ..Default::default()
+        };
+        let mut tx_b = Tx {
+            format: 2,
+            tags: tags(&[("a", "1"), ("b", "1"), ("a", "2")]),
+            ..Default::default()
+        };
+        let message = |tx: &Tx| deep_hash(tx.to_deep_hash_item().unwrap());
+        assert_ne!(message(&tx_a), message(&tx_b));
+
+        tx_a.sort_tags();
+        tx_b.sort_tags();
+        assert_eq!(tx_a.tags, tags(&[("a", "1"), ("a", "2"), ("b", "1")]));
+        assert_eq!(message(&tx_a), message(&tx_b));
+    }
+
+    #[test]
+    fn test_owner_jwk_string() {
+        let data = fs::read_to_string("res/sample_tx.json").unwrap();
+        let tx = Tx::from_str(&data).unwrap();
+
+        let jwk: JsonWebKey = tx.owner_jwk_string().parse().unwrap();
+        assert!(!jwk.key.is_private());
+        assert_eq!(
+            serde_json::to_value(&jwk).unwrap()["n"],
+            tx.owner.to_string()
+        );
+    }
+
+    #[test]
+    fn test_deep_hash_item_errors_propagate() {
+        let tx = Tx {
+            format: 2,
+            tags: vec![Tag::from_utf8_strs("name", "value").unwrap()],
+            ..Default::default()
+        };
+        assert!(tx.tags.to_deep_hash_item().is_ok());
+        assert!(tx.to_deep_hash_item().is_ok());
+
+        let tx = Tx { format: 3, ..tx };
+        assert!(matches!(
+            tx.to_deep_hash_item(),
+            Err(crate::error::Error::InvalidValueForTx)
+        ));
+    }
+
+    #[test]
+    fn test_invalid_tag_conversion() {
+        let data = fs::read_to_string("res/sample_tx.json").unwrap();
+        let mut json: serde_json::Value = serde_json::from_str(&data).unwrap();
+        json["tags"][0]["name"] = serde_json::json!("not base64url!");
+        assert!(matches!(
+            Tx::from_str(&json.to_string()),
+            Err(Error::SerdeJsonError(_))
+        ));
+
+        let tag = Tag {
+            name: Base64(b"name".to_vec()),
+            value: Base64(vec![0xff, 0xfe]),
+        };
+        assert!(matches!(
+            Tag::<String>::try_from(&tag),
+            Err(Error::FromUtf8Error(_))
+        ));
+    }
+
+    #[test]
+    fn test_quantity_bounds() {
+        let provider = Provider::default();
+        let new_tx = |quantity: u128| {
+            Tx::new(
+                &provider,
+                Base64(vec![1; 32]),
+                vec![],
+                quantity,
+                0,
+                Base64(vec![1; 32]),
+                vec![],
+                false,
+            )
+        };
+
+        let tx = new_tx(MAX_SUPPLY_WINSTONS).unwrap();
+        assert_eq!(tx.quantity.to_string(), MAX_SUPPLY_WINSTONS.to_string());
+
+        assert!(matches!(
+            new_tx(MAX_SUPPLY_WINSTONS + 1),
+            Err(Error::InvalidValueForTx)
+        ));
+        assert!(matches!(
+            new_tx(u128::MAX - 1),
+            Err(Error::InvalidValueForTx)
+        ));
+    }
+
+    #[test]
+    fn test_external_signature() {
+        let provider = Provider::default();
+        let mut tx = Tx::new(
+            &provider,
+            Base64::default(),
+            b"data".to_vec(),
+            0,
+            0,
+            Base64(vec![1; 32]),
+            vec![],
+            false,
+        )
+        .unwrap();
+
+        let message = tx.signing_message().unwrap();
+        let signature = provider.signer.sign(&message).unwrap();
+        tx.apply_signature(signature);
+
+        assert!(crate::verify::verify_transaction_full(&tx).is_ok());
+    }
+
+    #[test]
+    fn test_signing_message_rejects_format_1() {
+        let tx = Tx {
+            format: 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            tx.signing_message(),
+            Err(Error::InvalidValueForTx)
+        ));
+        assert!(tx.signature_data().is_ok());
+    }
+
+    #[test]
+    fn test_with_updated_metadata() {
+        let provider = Provider::default();
+        let data = fs::read("res/1mb.bin").unwrap();
+        let mut tx = Tx::new(
+            &provider,
+            Base64::default(),
+            data,
+            0,
+            10,
+            Base64(vec![1; 32]),
+            vec![],
+            false,
+        )
+        .unwrap();
+        tx.apply_signature(provider.sign(&tx.signature_data().unwrap()).unwrap());
+
+        let tags = vec![Tag::from_utf8_strs("App-Name", "arweave-rs").unwrap()];
+        let mut updated = tx.with_updated_metadata(tags.clone(), 20);
+        assert_eq!(updated.data_root, tx.data_root);
+        assert_eq!(updated.chunks.len(), tx.chunks.len());
+        assert_eq!(updated.tags, tags);
+        assert_eq!(updated.reward, 20);
+        assert!(updated.signature.is_empty());
+        assert!(updated.validate_data_root().is_ok());
+
+        updated.apply_signature(provider.sign(&updated.signature_data().unwrap()).unwrap());
+        assert!(crate::verify::verify_transaction_full(&updated).is_ok());
+        assert_ne!(updated.id, tx.id);
+    }
+
+    #[test]
+    fn test_validate_data_root() {
+        let provider = Provider::default();
+        let data = fs::read("res/1mb.bin").unwrap();
+        let mut tx = Tx::new(
+            &provider,
+            Base64::default(),
+            data,
+            0,
+            0,
+            Base64(vec![1; 32]),
+            vec![],
+            false,
+        )
+        .unwrap();
+        assert!(tx.chunks.len() > 1);
+        assert!(tx.validate_data_root().is_ok());
+
+        tx.proofs[1].proof[0] ^= 1;
+        assert!(matches!(tx.validate_data_root(), Err(Error::InvalidProof)));
+        tx.proofs[1].proof[0] ^= 1;
+
+        let last = tx.proofs.len() - 1;
+        let leaf = tx.proofs[last].proof.len() - 64;
+        tx.proofs[last].proof[leaf] ^= 1;
+        assert!(matches!(tx.validate_data_root(), Err(Error::InvalidProof)));
+        tx.proofs[last].proof[leaf] ^= 1;
+
+        tx.data.0[0] ^= 1;
+        assert!(matches!(
+            tx.validate_data_root(),
+            Err(Error::TransactionVerificationFailed(_))
+        ));
     }
 }
@@ -7,11 +7,13 @@ use crate::{currency::Currency, error::Error};
 use super::{tags::Tag, Tx};
 use crate::types::Tx as JsonTx;
 
-impl From<JsonTx> for Tx {
-    fn from(json_tx: JsonTx) -> Self {
+impl TryFrom<JsonTx> for Tx {
+    type Error = Error;
+
+    fn try_from(json_tx: JsonTx) -> Result<Self, Error> {
         let tags = json_tx.tags.iter().map(Tag::from).collect();
-        Tx {
-            quantity: Currency::from_str(&json_tx.quantity).unwrap(),
+        Ok(Tx {
+            quantity: Currency::from_str(&json_tx.quantity)?,
             format: json_tx.format,
             id: json_tx.id,
             last_tx: json_tx.last_tx,
@@ -20,12 +22,12 @@ impl From<JsonTx> for Tx {
             target: json_tx.target,
             data_root: json_tx.data_root,
             data: json_tx.data,
-            data_size: u64::from_str(&json_tx.data_size).unwrap(),
-            reward: u64::from_str(&json_tx.reward).unwrap(),
+            data_size: u64::from_str(&json_tx.data_size)?,
+            reward: u64::from_str(&json_tx.reward)?,
             signature: json_tx.signature,
             chunks: vec![],
             proofs: vec![],
-        }
+        })
     }
 }
 
@@ -34,7 +36,7 @@ impl FromStr for Tx {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let json_tx: JsonTx = serde_json::from_str(s).map_err(Error::SerdeJsonError)?;
-        Ok(Tx::from(json_tx))
+        Tx::try_from(json_tx)
     }
 }
 
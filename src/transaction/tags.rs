@@ -20,8 +20,18 @@ pub trait FromUtf8Strs<T> {
     fn from_utf8_strs(name: &str, value: &str) -> Result<T, Error>;
 }
 
+/// Arweave rejects tags with empty names. Control characters are rejected as well since
+/// they are almost certainly a mistake in a tag name.
+fn validate_tag_name(name: &str) -> Result<(), Error> {
+    if name.is_empty() || name.chars().any(char::is_control) {
+        return Err(Error::InvalidTagEncoding);
+    }
+    Ok(())
+}
+
 impl FromUtf8Strs<Tag<Base64>> for Tag<Base64> {
     fn from_utf8_strs(name: &str, value: &str) -> Result<Self, Error> {
+        validate_tag_name(name)?;
         let b64_name = Base64::from_utf8_str(name)?;
         let b64_value = Base64::from_utf8_str(value)?;
 
@@ -34,6 +44,7 @@ impl FromUtf8Strs<Tag<Base64>> for Tag<Base64> {
 
 impl FromUtf8Strs<Tag<String>> for Tag<String> {
     fn from_utf8_strs(name: &str, value: &str) -> Result<Self, Error> {
+        validate_tag_name(name)?;
         let name = String::from(name);
         let value = String::from(value);
 
@@ -45,8 +56,8 @@ impl<'a> ToItems<'a, Vec<Tag<Base64>>> for Vec<Tag<Base64>> {
     fn to_deep_hash_item(&'a self) -> Result<DeepHashItem, Error> {
         Ok(DeepHashItem::List(
             self.iter()
-                .map(|t| t.to_deep_hash_item().unwrap())
-                .collect(),
+                .map(|t| t.to_deep_hash_item())
+                .collect::<Result<Vec<_>, Error>>()?,
         ))
     }
 }
@@ -73,6 +84,17 @@ impl Serialize for Tag<Base64> {
     }
 }
 
+impl TryFrom<&Tag<Base64>> for Tag<String> {
+    type Error = Error;
+
+    fn try_from(tag: &Tag<Base64>) -> Result<Self, Self::Error> {
+        Ok(Tag {
+            name: tag.name.to_utf8_string()?,
+            value: tag.value.to_utf8_string()?,
+        })
+    }
+}
+
 impl From<&BaseTag> for Tag<Base64> {
     fn from(base_tag: &BaseTag) -> Self {
         Tag {
@@ -81,3 +103,45 @@ impl From<&BaseTag> for Tag<Base64> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{crypto::base64::Base64, error::Error};
+
+    use super::{FromUtf8Strs, Tag};
+
+    #[test]
+    fn test_empty_tag_name() {
+        assert!(matches!(
+            Tag::<Base64>::from_utf8_strs("", "value"),
+            Err(Error::InvalidTagEncoding)
+        ));
+        assert!(matches!(
+            Tag::<String>::from_utf8_strs("", "value"),
+            Err(Error::InvalidTagEncoding)
+        ));
+    }
+
+    #[test]
+    fn test_control_characters_in_tag_name() {
+        assert!(matches!(
+            Tag::<Base64>::from_utf8_strs("App\nName", "value"),
+            Err(Error::InvalidTagEncoding)
+        ));
+    }
+
+    #[test]
+    fn test_empty_tag_value() {
+        let tag = Tag::<String>::from_utf8_strs("name", "").unwrap();
+        assert_eq!(tag.value, "");
+        let tag = Tag::<Base64>::from_utf8_strs("name", "").unwrap();
+        assert!(tag.value.is_empty());
+    }
+
+    #[test]
+    fn test_valid_tag() {
+        let tag = Tag::<Base64>::from_utf8_strs("Content-Type", "text/plain").unwrap();
+        assert_eq!(tag.name.0, b"Content-Type".to_vec());
+        assert_eq!(tag.value.0, b"text/plain".to_vec());
+    }
+}
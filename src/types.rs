@@ -1,14 +1,17 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use serde_aux::prelude::*;
 
-use crate::crypto::base64::Base64;
+use crate::{
+    crypto::{base64::Base64, merkle::Proof},
+    error::Error,
+};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct NetworkInfo {
     pub network: String,
     pub version: usize,
     pub release: usize,
-    pub height: u128,
+    pub height: u64,
     pub current: Base64,
     pub blocks: usize,
     pub peers: usize,
@@ -16,7 +19,7 @@ pub struct NetworkInfo {
     pub node_state_latency: usize,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct ProofOfAccess {
     pub option: String,
     pub tx_path: Base64,
@@ -25,7 +28,7 @@ pub struct ProofOfAccess {
 }
 
 //Defined in https://docs.arweave.org/developers/server/http-api#block-format
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct BlockInfo {
     pub nonce: Base64,
     pub previous_block: Base64,
@@ -75,18 +78,153 @@ pub struct Tag {
     pub value: Base64,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct TxStatus {
-    pub block_height: u128,
+    pub block_height: u64,
     pub block_indep_hash: Base64,
     pub number_of_confirmations: u64,
 }
 
+/// Individual checks run by [`crate::Arweave::verify_transaction_full`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationCheck {
+    Signature,
+    Id,
+    DataRoot,
+}
+
+/// State of a transaction as reported by the `/tx/{id}/status` endpoint.
+#[derive(Debug)]
+pub enum ConfirmationState {
+    NotFound,
+    Pending,
+    Confirmed(TxStatus),
+}
+
+/// Transaction fields served individually by the `/tx/{id}/{field}` endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxField {
+    Id,
+    LastTx,
+    Owner,
+    Tags,
+    Target,
+    Quantity,
+    DataRoot,
+    DataSize,
+    Reward,
+    Signature,
+}
+
+impl TxField {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TxField::Id => "id",
+            TxField::LastTx => "last_tx",
+            TxField::Owner => "owner",
+            TxField::Tags => "tags",
+            TxField::Target => "target",
+            TxField::Quantity => "quantity",
+            TxField::DataRoot => "data_root",
+            TxField::DataSize => "data_size",
+            TxField::Reward => "reward",
+            TxField::Signature => "signature",
+        }
+    }
+}
+
+/// A chunk of transaction data together with its merkle proof, as posted to the `/chunk`
+/// endpoint. Built with `Tx::get_chunk` and posted with `Uploader::post_chunk`.
+///
+/// Gateways expect `data_size` and `offset` as strings, so they are serialized that way and
+/// accepted either as strings or numbers when parsed.
 #[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
 pub struct Chunk {
+    /// Merkle root of the transaction data the chunk belongs to.
     pub data_root: Base64,
+    /// Size in bytes of the whole transaction data.
+    #[serde(
+        serialize_with = "serialize_as_string",
+        deserialize_with = "deserialize_number_from_string"
+    )]
     pub data_size: u64,
+    /// Merkle proof of the chunk against `data_root`.
     pub data_path: Base64,
+    /// Offset of the last byte of the chunk within the transaction data.
+    #[serde(
+        serialize_with = "serialize_as_string",
+        deserialize_with = "deserialize_number_from_string"
+    )]
     pub offset: usize,
+    /// The chunk bytes.
+    pub chunk: Base64,
+}
+
+impl Chunk {
+    /// Builds the chunk covering `data[min..max]`, where `data` is the whole transaction data
+    /// and `proof` the proof of that chunk against `data_root`, e.g. as generated by
+    /// `merkle::resolve_proofs`.
+    ///
+    /// Panics if `min..max` is out of the bounds of `data`.
+    pub fn build(data_root: Base64, data: &[u8], min: usize, max: usize, proof: &Proof) -> Chunk {
+        Chunk {
+            data_root,
+            data_size: data.len() as u64,
+            data_path: Base64(proof.proof.clone()),
+            offset: proof.offset,
+            chunk: Base64(data[min..max].to_vec()),
+        }
+    }
+
+    /// Size in bytes of the chunk data.
+    pub fn size(&self) -> usize {
+        self.chunk.0.len()
+    }
+}
+
+fn serialize_as_string<T: ToString, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+/// Response of the `/tx/{id}/offset` endpoint. `offset` is the absolute weave offset of
+/// the last byte of the transaction data.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub struct TxOffset {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub size: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub offset: u64,
+}
+
+impl TxOffset {
+    /// Absolute weave offset of the first byte of the transaction data. Fails if `size` is
+    /// larger than the weave up to `offset`, which no node would report for a real
+    /// transaction.
+    pub fn start(&self) -> Result<u64, Error> {
+        self.offset
+            .checked_add(1)
+            .and_then(|end| end.checked_sub(self.size))
+            .ok_or(Error::OffsetOutOfRange(
+                self.offset as usize,
+                self.size as usize,
+            ))
+    }
+}
+
+/// Response of the `/chunk/{offset}` endpoint.
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+pub struct ChunkResponse {
     pub chunk: Base64,
+    pub data_path: Base64,
+    pub tx_path: Base64,
+}
+
+/// Response of the gateway ArNS resolver endpoint.
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ArnsRecord {
+    pub tx_id: Base64,
 }
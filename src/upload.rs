@@ -1,59 +1,212 @@
-use std::{str::FromStr, thread::sleep, time::Duration};
+use std::{
+    future::Future,
+    pin::Pin,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
 
+use futures_timer::Delay;
 use reqwest::{
     header::{ACCEPT, CONTENT_TYPE},
     Client,
 };
+use tokio::{
+    sync::{watch, Semaphore},
+    task::JoinHandle,
+};
 
 use crate::{
-    consts::{ARWEAVE_BASE_URL, CHUNKS_RETRIES, CHUNKS_RETRY_SLEEP},
+    client::{http_client, normalize_base_url},
+    consts::{ARWEAVE_BASE_URL, CHUNKS_RETRIES, CHUNKS_RETRY_BUDGET, CHUNKS_RETRY_SLEEP},
     error::Error,
     types::Chunk,
 };
 
+/// Number of chunks of a transaction's data that have been uploaded so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UploadProgress {
+    pub uploaded_chunks: usize,
+    pub total_chunks: usize,
+}
+
+/// Handle to an upload running in the background, as returned by `Arweave::spawn_upload`.
+///
+/// Awaiting the handle resolves to the transaction id and reward once the upload completes.
+pub struct UploadHandle {
+    task: JoinHandle<Result<(String, u64), Error>>,
+    progress: watch::Receiver<UploadProgress>,
+}
+
+impl UploadHandle {
+    pub(crate) fn new(
+        task: JoinHandle<Result<(String, u64), Error>>,
+        progress: watch::Receiver<UploadProgress>,
+    ) -> Self {
+        UploadHandle { task, progress }
+    }
+
+    /// Returns a receiver notified each time more chunks have been uploaded.
+    pub fn progress(&self) -> watch::Receiver<UploadProgress> {
+        self.progress.clone()
+    }
+}
+
+impl Future for UploadHandle {
+    type Output = Result<(String, u64), Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.task).poll(cx).map(|res| match res {
+            Ok(res) => res,
+            Err(err) => Err(Error::UploadTaskFailed(err.to_string())),
+        })
+    }
+}
+
+/// Number of chunk retries left for an upload, shared by all of its chunks.
+#[derive(Debug)]
+pub struct RetryBudget {
+    max_retries: usize,
+    remaining: AtomicUsize,
+}
+
+impl RetryBudget {
+    pub fn new(max_retries: usize) -> Self {
+        RetryBudget {
+            max_retries,
+            remaining: AtomicUsize::new(max_retries),
+        }
+    }
+
+    /// Takes one retry from the budget, returning `false` once it's used up.
+    pub fn try_spend(&self) -> bool {
+        self.remaining
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |remaining| {
+                remaining.checked_sub(1)
+            })
+            .is_ok()
+    }
+}
+
+#[derive(Clone)]
 pub struct Uploader {
     url: url::Url,
+    max_in_flight_bytes: Option<usize>,
+    in_flight_permits: Arc<Semaphore>,
+    retry_budget: usize,
+    client: Client,
 }
 
 impl Default for Uploader {
     fn default() -> Self {
         let url = url::Url::from_str(ARWEAVE_BASE_URL).unwrap();
-        Self { url }
+        Self::new(url)
     }
 }
 
 impl Uploader {
     pub fn new(url: url::Url) -> Self {
-        Uploader { url }
+        Uploader {
+            url: normalize_base_url(url),
+            max_in_flight_bytes: None,
+            in_flight_permits: Arc::new(Semaphore::new(0)),
+            retry_budget: CHUNKS_RETRY_BUDGET,
+            client: http_client(),
+        }
+    }
+
+    /// Posts chunks with `client`, so that its connection pool is shared with other clients.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Limits the number of chunk retries across a whole upload to `max_retries`.
+    pub fn with_retry_budget(mut self, max_retries: usize) -> Self {
+        self.retry_budget = max_retries;
+        self
+    }
+
+    /// Returns a fresh retry budget for a new upload.
+    pub fn retry_budget(&self) -> RetryBudget {
+        RetryBudget::new(self.retry_budget)
     }
 
-    pub async fn post_chunk_with_retries(
+    /// Limits the total size of chunks being posted at the same time to `max_bytes`,
+    /// in addition to the limit on the number of concurrent requests.
+    pub fn with_max_in_flight_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_in_flight_bytes = Some(max_bytes);
+        self.in_flight_permits = Arc::new(Semaphore::new(max_bytes));
+        self
+    }
+
+    /// Returns the total size of chunks currently being posted, if limited.
+    pub fn in_flight_bytes(&self) -> Option<usize> {
+        self.max_in_flight_bytes
+            .map(|max_bytes| max_bytes - self.in_flight_permits.available_permits())
+    }
+
+    pub async fn post_chunk_with_retries(&self, chunk: Chunk) -> Result<usize, Error> {
+        self.post_chunk_with_budget(chunk, &RetryBudget::new(usize::MAX))
+            .await
+    }
+
+    /// Posts a chunk, retrying up to [`CHUNKS_RETRIES`] times as long as `budget` allows.
+    pub async fn post_chunk_with_budget(
         &self,
         chunk: Chunk,
-        client: Client,
+        budget: &RetryBudget,
     ) -> Result<usize, Error> {
         let mut retries = 0;
-        let mut resp = self.post_chunk(&chunk, &client).await;
+        let mut resp = self.post_chunk(&chunk).await;
 
         while retries < CHUNKS_RETRIES {
             match resp {
                 Ok(offset) => return Ok(offset),
+                Err(_) if !budget.try_spend() => {
+                    return Err(Error::RetryBudgetExhausted(budget.max_retries))
+                }
                 Err(e) => {
-                    dbg!("post_chunk_with_retries: {:?}", e);
-                    sleep(Duration::from_secs(CHUNKS_RETRY_SLEEP));
+                    tracing::warn!(
+                        offset = chunk.offset,
+                        attempt = retries + 1,
+                        error = %e,
+                        "retrying chunk"
+                    );
+                    Delay::new(Duration::from_secs(CHUNKS_RETRY_SLEEP)).await;
                     retries += 1;
-                    resp = self.post_chunk(&chunk, &client).await;
+                    resp = self.post_chunk(&chunk).await;
                 }
             }
         }
         resp
     }
 
-    pub async fn post_chunk(&self, chunk: &Chunk, client: &Client) -> Result<usize, Error> {
-        let url = self.url.join("chunk").map_err(Error::UrlParseError)?;
-        // let client = reqwest::Client::new();
-
-        let resp = client
+    pub async fn post_chunk(&self, chunk: &Chunk) -> Result<usize, Error> {
+        let _permit = match self.max_in_flight_bytes {
+            Some(max_bytes) => {
+                // A chunk larger than the limit would never get enough permits.
+                let bytes = chunk.chunk.0.len().min(max_bytes) as u32;
+                Some(
+                    self.in_flight_permits
+                        .acquire_many(bytes)
+                        .await
+                        .map_err(|e| Error::PostChunkError(e.to_string()))?,
+                )
+            }
+            None => None,
+        };
+        let url = self.url.join("chunk")?;
+        let resp = self
+            .client
             .post(url)
             .json(&chunk)
             .header(&ACCEPT, "application/json")
@@ -68,3 +221,139 @@ impl Uploader {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::{stream, StreamExt};
+    use httpmock::{Method::POST, MockServer};
+    use serde_json::json;
+
+    use crate::{
+        crypto::base64::Base64,
+        crypto::merkle::{
+            generate_data_root, generate_leaves, resolve_proofs, validate_chunk, MAX_CHUNK_SIZE,
+        },
+        types::Chunk,
+    };
+
+    use super::Uploader;
+
+    #[tokio::test]
+    async fn test_max_in_flight_bytes() {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/chunk");
+                then.status(200).delay(Duration::from_millis(200));
+            })
+            .await;
+
+        let max_bytes = 2 * MAX_CHUNK_SIZE;
+        let url = url::Url::parse(&server.url("/")).unwrap();
+        let uploader = Uploader::new(url).with_max_in_flight_bytes(max_bytes);
+        let chunks: Vec<Chunk> = (0..6)
+            .map(|i| Chunk {
+                offset: (i + 1) * MAX_CHUNK_SIZE - 1,
+                chunk: Base64(vec![0; MAX_CHUNK_SIZE]),
+                ..Default::default()
+            })
+            .collect();
+
+        let upload = stream::iter(chunks.iter())
+            .map(|chunk| uploader.post_chunk(chunk))
+            .buffer_unordered(chunks.len())
+            .collect::<Vec<_>>();
+        tokio::pin!(upload);
+
+        let mut max_observed = 0;
+        let results = loop {
+            tokio::select! {
+                results = &mut upload => break results,
+                _ = tokio::time::sleep(Duration::from_millis(10)) => {
+                    max_observed = max_observed.max(uploader.in_flight_bytes().unwrap());
+                }
+            }
+        };
+
+        assert!(results.iter().all(|res| res.is_ok()));
+        mock.assert_hits_async(chunks.len()).await;
+        assert!(max_observed > 0);
+        assert!(max_observed <= max_bytes);
+    }
+
+    #[tokio::test]
+    async fn test_post_built_chunk() {
+        let data = std::fs::read("res/rebar3").unwrap();
+        let leaves = generate_leaves(data.clone()).unwrap();
+        let root = generate_data_root(leaves.clone()).unwrap();
+        let data_root = Base64(root.id.to_vec());
+        let root_id = root.id;
+        let proofs = resolve_proofs(root, None).unwrap();
+        let node = &leaves[1];
+        let chunk = Chunk::build(
+            data_root.clone(),
+            &data,
+            node.min_byte_range,
+            node.max_byte_range,
+            &proofs[1],
+        );
+        assert_eq!(chunk.size(), node.max_byte_range - node.min_byte_range);
+        assert!(validate_chunk(root_id, node.clone(), proofs[1].clone()).is_ok());
+
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/chunk").json_body_partial(
+                    json!({
+                        "data_root": data_root.to_string(),
+                        "data_size": data.len().to_string(),
+                        "offset": proofs[1].offset.to_string(),
+                    })
+                    .to_string(),
+                );
+                then.status(200);
+            })
+            .await;
+        let uploader = Uploader::new(url::Url::parse(&server.url("/")).unwrap());
+        let offset = uploader.post_chunk(&chunk).await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(offset, node.max_byte_range - 1);
+    }
+
+    #[tokio::test]
+    async fn test_post_chunk_json_shape() {
+        let chunk = Chunk {
+            data_root: Base64(vec![1; 32]),
+            data_size: 300_000,
+            data_path: Base64(vec![2; 64]),
+            offset: MAX_CHUNK_SIZE - 1,
+            chunk: Base64(b"chunk".to_vec()),
+        };
+        let expected = json!({
+            "data_root": Base64(vec![1; 32]).to_string(),
+            "data_size": "300000",
+            "data_path": Base64(vec![2; 64]).to_string(),
+            "offset": (MAX_CHUNK_SIZE - 1).to_string(),
+            "chunk": Base64(b"chunk".to_vec()).to_string(),
+        });
+        assert_eq!(serde_json::to_value(&chunk).unwrap(), expected);
+        let parsed: Chunk = serde_json::from_value(expected.clone()).unwrap();
+        assert_eq!(parsed, chunk);
+
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/chunk").json_body(expected);
+                then.status(200);
+            })
+            .await;
+        let uploader = Uploader::new(url::Url::parse(&server.url("/")).unwrap());
+        let offset = uploader.post_chunk(&chunk).await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(offset, chunk.offset);
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{crypto::base64::Base64, error::Error, transaction::Tx, verify};
+
+/// Verifies signatures and transactions without holding any private key material.
+#[derive(Debug, Default, Clone)]
+pub struct Verifier {
+    pub_key: Option<Base64>,
+}
+
+impl Verifier {
+    /// Creates a verifier that checks transactions against their embedded owner.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates a verifier bound to a public key (RSA modulus).
+    pub fn from_public_key(pub_key: Base64) -> Self {
+        Self {
+            pub_key: Some(pub_key),
+        }
+    }
+
+    pub fn public_key(&self) -> Option<&Base64> {
+        self.pub_key.as_ref()
+    }
+
+    /// Verifies `signature` over `message` with the verifier's public key.
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), Error> {
+        match &self.pub_key {
+            Some(pub_key) => verify::verify(&pub_key.0, message, signature),
+            None => Err(Error::NoneError("public key".to_owned())),
+        }
+    }
+
+    /// Verifies a transaction signature. If the verifier is bound to a public key,
+    /// the transaction owner must also match it.
+    pub fn verify_transaction(&self, transaction: &Tx) -> Result<(), Error> {
+        if let Some(pub_key) = &self.pub_key {
+            if pub_key != &transaction.owner {
+                return Err(Error::InvalidSignature);
+            }
+        }
+        verify::verify_transaction(transaction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, str::FromStr};
+
+    use crate::{crypto::base64::Base64, error::Error, transaction::Tx};
+
+    use super::Verifier;
+
+    fn sample_tx() -> Tx {
+        let data = fs::read_to_string("res/sample_tx.json").unwrap();
+        Tx::from_str(&data).unwrap()
+    }
+
+    #[test]
+    fn test_verify_transaction_with_owner_key() -> Result<(), Error> {
+        let tx = sample_tx();
+        let verifier = Verifier::from_public_key(tx.owner.clone());
+        verifier.verify_transaction(&tx)
+    }
+
+    #[test]
+    fn test_verify_transaction_without_key() -> Result<(), Error> {
+        Verifier::new().verify_transaction(&sample_tx())
+    }
+
+    #[test]
+    fn test_verify_transaction_with_other_key() {
+        let verifier = Verifier::from_public_key(Base64(vec![1; 512]));
+        assert!(matches!(
+            verifier.verify_transaction(&sample_tx()),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_verify_without_key() {
+        assert!(matches!(
+            Verifier::new().verify(b"message", b"signature"),
+            Err(Error::NoneError(_))
+        ));
+    }
+}
@@ -1,34 +1,52 @@
 use crate::{
-    crypto::hash::{deep_hash, ToItems},
+    crypto::{
+        hash::sha256,
+        merkle::{generate_data_root, generate_leaves},
+        utils::{public_jwk_string, PssParams, PSS_PARAMS},
+    },
     error::Error,
-    transaction::Tx,
+    transaction::{tags::Tag, SignatureFields, Tx},
+    types::{Tx as JsonTx, VerificationCheck},
 };
-use data_encoding::BASE64URL;
 use jsonwebkey::JsonWebKey;
-use rand::thread_rng;
-use rsa::{pkcs8::DecodePublicKey, PaddingScheme, PublicKey, RsaPublicKey};
+use rsa::{pkcs8::DecodePublicKey, PublicKey, PublicKeyParts, RsaPublicKey};
 use sha2::Digest;
+use std::str::FromStr;
 
 pub fn verify(pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), Error> {
-    let jwt_str = format!(
-        "{{\"kty\":\"RSA\",\"e\":\"AQAB\",\"n\":\"{}\"}}",
-        BASE64URL.encode(pub_key)
-    );
-    let jwk: JsonWebKey = jwt_str.parse().unwrap();
+    verify_with_params(pub_key, message, signature, &PSS_PARAMS)
+}
+
+pub fn verify_with_params(
+    pub_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+    params: &PssParams,
+) -> Result<(), Error> {
+    // An empty or malformed owner can't have produced any signature.
+    if pub_key.is_empty() {
+        return Err(Error::InvalidSignature);
+    }
+    let jwt_str = public_jwk_string(pub_key);
+    let jwk: JsonWebKey = jwt_str.parse().map_err(|_| Error::InvalidSignature)?;
+
+    let pub_key = RsaPublicKey::from_public_key_der(jwk.key.to_der().as_slice())
+        .map_err(|_| Error::InvalidSignature)?;
+    if signature.len() != pub_key.size() {
+        tracing::warn!(
+            expected = pub_key.size(),
+            actual = signature.len(),
+            "signature length doesn't match the key size"
+        );
+        return Err(Error::InvalidSignature);
+    }
 
-    let pub_key = RsaPublicKey::from_public_key_der(jwk.key.to_der().as_slice()).unwrap();
     let mut hasher = sha2::Sha256::new();
     hasher.update(message);
     let hashed = &hasher.finalize();
 
-    let rng = thread_rng();
-    let padding = PaddingScheme::PSS {
-        salt_rng: Box::new(rng),
-        digest: Box::new(sha2::Sha256::new()),
-        salt_len: None,
-    };
     pub_key
-        .verify(padding, hashed, signature)
+        .verify(params.padding_scheme(), hashed, signature)
         .map(|_| ())
         .map_err(|_| Error::InvalidSignature)
 }
@@ -38,29 +56,267 @@ pub fn verify_transaction(transaction: &Tx) -> Result<(), Error> {
         return Err(Error::UnsignedTransaction);
     }
 
-    let deep_hash_item = transaction.to_deep_hash_item()?;
-    let message = deep_hash(deep_hash_item);
-    let signature = &transaction.signature;
+    let message = transaction.signature_data()?;
+    verify(&transaction.owner.0, &message, &transaction.signature.0)
+}
 
-    let jwt_str = format!(
-        "{{\"kty\":\"RSA\",\"e\":\"AQAB\",\"n\":\"{}\"}}",
-        BASE64URL.encode(&transaction.owner.0)
-    );
-    let jwk: JsonWebKey = jwt_str.parse().unwrap();
+/// Verifies the signature and that the id is the sha256 hash of the signature, so that a
+/// validly signed transaction can't be passed off under another id.
+pub fn verify_transaction_with_id(transaction: &Tx) -> Result<(), Error> {
+    let failed = Error::TransactionVerificationFailed;
 
-    let pub_key = RsaPublicKey::from_public_key_der(jwk.key.to_der().as_slice()).unwrap();
-    let mut hasher = sha2::Sha256::new();
-    hasher.update(message);
-    let hashed = &hasher.finalize();
+    verify_transaction(transaction).map_err(|_| failed(VerificationCheck::Signature))?;
+
+    if transaction.id.0 != sha256(&transaction.signature.0) {
+        return Err(failed(VerificationCheck::Id));
+    }
+    Ok(())
+}
+
+/// Verifies the signature, the id and, when the data is included, the data root of a
+/// transaction, reporting the first check that fails. Format 1 transactions have no data
+/// root, their data is covered by the signature directly.
+pub fn verify_transaction_full(transaction: &Tx) -> Result<(), Error> {
+    let failed = Error::TransactionVerificationFailed;
+
+    verify_transaction_with_id(transaction)?;
+
+    // Empty data has an empty data root, so a root without data points at data that
+    // doesn't belong to the transaction.
+    if transaction.data_size == 0 && !transaction.data_root.is_empty() {
+        return Err(failed(VerificationCheck::DataRoot));
+    }
+
+    if transaction.format == 2 && !transaction.data.is_empty() {
+        let root = generate_data_root(generate_leaves(transaction.data.0.clone())?)?;
+        if root.id[..] != transaction.data_root.0[..] {
+            return Err(failed(VerificationCheck::DataRoot));
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses a transaction as serialized by other implementations (gateways, arweave-js)
+/// and fully verifies it, returning the parsed transaction.
+pub fn verify_external_tx(json: &str) -> Result<Tx, Error> {
+    let transaction = Tx::from_str(json)?;
+    verify_transaction_full(&transaction)?;
+    Ok(transaction)
+}
+
+/// Verifies a transaction as returned by `/tx/{id}` without converting it to [`Tx`]. The
+/// numeric fields are signed as strings, so they are used exactly as received.
+pub fn verify_json_tx(json: &str) -> Result<(), Error> {
+    let transaction: JsonTx = serde_json::from_str(json).map_err(Error::SerdeJsonError)?;
+    let failed = Error::TransactionVerificationFailed;
+
+    if transaction.signature.is_empty() {
+        return Err(Error::UnsignedTransaction);
+    }
+    let message = json_tx_signature_data(&transaction)?;
+    verify(&transaction.owner.0, &message, &transaction.signature.0)
+        .map_err(|_| failed(VerificationCheck::Signature))?;
+
+    if transaction.id.0 != sha256(&transaction.signature.0) {
+        return Err(failed(VerificationCheck::Id));
+    }
+
+    if transaction.data_size == "0" && !transaction.data_root.is_empty() {
+        return Err(failed(VerificationCheck::DataRoot));
+    }
+
+    if transaction.format == 2 && !transaction.data.is_empty() {
+        let root = generate_data_root(generate_leaves(transaction.data.0.clone())?)?;
+        if root.id[..] != transaction.data_root.0[..] {
+            return Err(failed(VerificationCheck::DataRoot));
+        }
+    }
+
+    Ok(())
+}
 
-    let rng = thread_rng();
-    let padding = PaddingScheme::PSS {
-        salt_rng: Box::new(rng),
-        digest: Box::new(sha2::Sha256::new()),
-        salt_len: None,
+/// Same as [`Tx::signature_data`], over the fields of the JSON representation.
+fn json_tx_signature_data(transaction: &JsonTx) -> Result<Vec<u8>, Error> {
+    SignatureFields {
+        format: transaction.format,
+        owner: &transaction.owner.0,
+        target: &transaction.target.0,
+        data: &transaction.data.0,
+        quantity: transaction.quantity.clone(),
+        reward: transaction.reward.clone(),
+        last_tx: &transaction.last_tx.0,
+        tags: transaction.tags.iter().map(Tag::from).collect(),
+        data_size: transaction.data_size.clone(),
+        data_root: &transaction.data_root.0,
+    }
+    .signature_data()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, str::FromStr};
+
+    use crate::{
+        crypto::base64::Base64,
+        crypto::{sign::Signer, utils::PssParams},
+        error::Error,
+        transaction::Tx,
+        types::VerificationCheck,
     };
-    pub_key
-        .verify(padding, hashed, &signature.0)
-        .map(|_| ())
-        .map_err(|_| Error::InvalidSignature)
+
+    use super::{
+        verify_external_tx, verify_json_tx, verify_transaction, verify_transaction_full,
+        verify_transaction_with_id, verify_with_params,
+    };
+
+    #[test]
+    fn test_verify_truncated_signature() {
+        let data = fs::read_to_string("res/sample_tx.json").unwrap();
+        let mut tx = Tx::from_str(&data).unwrap();
+        tx.signature.0.truncate(100);
+
+        assert!(matches!(
+            verify_transaction(&tx),
+            Err(Error::InvalidSignature)
+        ));
+        assert!(matches!(
+            crate::crypto::verify::verify(
+                &tx.owner.0,
+                &tx.signature_data().unwrap(),
+                &tx.signature.0
+            ),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_verify_empty_owner() {
+        let data = fs::read_to_string("res/sample_tx.json").unwrap();
+        let mut tx = Tx::from_str(&data).unwrap();
+        tx.owner = Base64::empty();
+        assert!(matches!(
+            verify_transaction(&tx),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_verify_tampered_id() {
+        let data = fs::read_to_string("res/sample_tx.json").unwrap();
+        let mut tx = Tx::from_str(&data).unwrap();
+        assert!(verify_transaction_with_id(&tx).is_ok());
+
+        tx.id.0[0] ^= 1;
+
+        // The id isn't signed, so the signature alone still verifies.
+        assert!(verify_transaction(&tx).is_ok());
+        assert!(matches!(
+            verify_transaction_with_id(&tx),
+            Err(Error::TransactionVerificationFailed(VerificationCheck::Id))
+        ));
+    }
+
+    #[test]
+    fn test_sign_verify_with_fixed_salt() {
+        let signer = Signer::default();
+        let message = b"message".to_vec();
+        let params = PssParams { salt_len: Some(32) };
+
+        let signature = signer.sign_with_params(&message, &params).unwrap();
+        let pub_key = signer.public_key();
+
+        assert!(verify_with_params(&pub_key.0, &message, &signature.0, &params).is_ok());
+        assert!(matches!(
+            verify_with_params(&pub_key.0, b"other message", &signature.0, &params),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_verify_format_1_transaction() {
+        // signed with res/test_wallet.json over the concatenated format 1 fields
+        let data = fs::read_to_string("res/sample_tx_v1.json").unwrap();
+        let tx = Tx::from_str(&data).unwrap();
+        assert_eq!(tx.format, 1);
+
+        assert!(verify_transaction(&tx).is_ok());
+        assert!(super::verify_transaction_full(&tx).is_ok());
+    }
+
+    #[test]
+    fn test_verify_external_tx() {
+        // sample_tx_with_data.json is a format 2 transaction with inline data, signed with
+        // res/test_wallet.json.
+        for path in ["res/sample_tx.json", "res/sample_tx_with_data.json"] {
+            let data = fs::read_to_string(path).unwrap();
+            assert!(verify_external_tx(&data).is_ok(), "{}", path);
+        }
+
+        let data = fs::read_to_string("res/sample_tx_with_data.json").unwrap();
+        let tx = verify_external_tx(&data).unwrap();
+        assert_eq!(tx.data.0, b"Hello from a test vector".to_vec());
+        assert_eq!(tx.get_tag("App-Name"), Some("arweave-rs-tests".to_string()));
+
+        let tampered = data.replace(&tx.data.to_string(), "SGVsbG8gZnJvbSBhcndlYXZlLXJz");
+        assert!(matches!(
+            verify_external_tx(&tampered),
+            Err(Error::TransactionVerificationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_reject_data_root_without_data() {
+        let signer = Signer::default();
+        let sign = |tx: &mut Tx| {
+            let signature = signer.sign(&tx.signature_data().unwrap()).unwrap();
+            tx.apply_signature(signature);
+        };
+        let mut tx = Tx {
+            format: 2,
+            owner: signer.public_key(),
+            last_tx: Base64(vec![0; 32]),
+            data_root: Base64(vec![1; 32]),
+            ..Default::default()
+        };
+        sign(&mut tx);
+
+        assert!(verify_transaction(&tx).is_ok());
+        assert!(matches!(
+            verify_transaction_full(&tx),
+            Err(Error::TransactionVerificationFailed(
+                VerificationCheck::DataRoot
+            ))
+        ));
+
+        tx.data_root = Base64::empty();
+        sign(&mut tx);
+        assert!(verify_transaction_full(&tx).is_ok());
+    }
+
+    #[test]
+    fn test_verify_json_tx() {
+        for path in [
+            "res/sample_tx.json",
+            "res/sample_tx_v1.json",
+            "res/sample_tx_with_data.json",
+        ] {
+            let data = fs::read_to_string(path).unwrap();
+            assert!(verify_json_tx(&data).is_ok(), "{}", path);
+        }
+
+        let data = fs::read_to_string("res/sample_tx.json").unwrap();
+        let tx = Tx::from_str(&data).unwrap();
+        let tampered = data.replace(
+            &format!("\"reward\": \"{}\"", tx.reward),
+            &format!("\"reward\": \"{}\"", tx.reward + 1),
+        );
+        assert_ne!(tampered, data);
+        assert!(matches!(
+            verify_json_tx(&tampered),
+            Err(Error::TransactionVerificationFailed(
+                VerificationCheck::Signature
+            ))
+        ));
+    }
 }
@@ -1,13 +1,17 @@
 use pretend::{interceptor::NoopRequestInterceptor, pretend, resolver::UrlResolver, Pretend, Url};
 
-use crate::{client::Client, error::Error};
+use crate::{
+    client::{normalize_base_url, Client},
+    currency::Currency,
+    error::Error,
+};
 
 #[pretend]
 trait TransactionInfoFetch {
-    #[request(method = "GET", path = "/wallet/{address}/balance")]
+    #[request(method = "GET", path = "wallet/{address}/balance")]
     async fn wallet_balance(&self, address: &str) -> pretend::Result<String>;
 
-    #[request(method = "GET", path = "/wallet/{address}/last_tx")]
+    #[request(method = "GET", path = "wallet/{address}/last_tx")]
     async fn wallet_last_tx_id(&self, address: &str) -> pretend::Result<String>;
 }
 
@@ -16,7 +20,7 @@ pub struct WalletInfoClient(Pretend<Client, UrlResolver, NoopRequestInterceptor>
 impl WalletInfoClient {
     pub fn new(url: Url) -> Self {
         let client = Client::default();
-        let pretend = Pretend::for_client(client).with_url(url);
+        let pretend = Pretend::for_client(client).with_url(normalize_base_url(url));
         Self(pretend)
     }
 
@@ -27,6 +31,17 @@ impl WalletInfoClient {
             .map_err(|op| Error::WalletError(op.to_string()))
     }
 
+    /// Returns the balance in whole AR, for display. The winston balance is converted
+    /// through [`Currency`] so large balances are split into AR and winston before being
+    /// turned into an `f64`.
+    pub async fn balance_ar(&self, address: &str) -> Result<f64, Error> {
+        let winstons = self.balance(address).await?.trim().parse::<u128>()?;
+        Currency::from(winstons)
+            .to_ar_string()
+            .parse::<f64>()
+            .map_err(|err| Error::WalletError(err.to_string()))
+    }
+
     pub async fn last_tx_id(&self, address: &str) -> Result<String, Error> {
         self.0
             .wallet_last_tx_id(address)
@@ -64,6 +79,27 @@ mod tests {
         assert_eq!(tx_info, "123123".to_string());
     }
 
+    #[test]
+    fn test_balance_ar() {
+        let address = "address";
+        let server = MockServer::start();
+        let server_url = server.url("");
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/wallet/{}/balance", address));
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body("1500000000000");
+        });
+
+        let url = Url::parse(&server_url).unwrap();
+        let client = WalletInfoClient::new(url);
+        let balance = block_on(client.balance_ar(address)).unwrap();
+
+        mock.assert();
+        assert_eq!(balance, 1.5);
+    }
+
     #[test]
     fn test_last_tx() {
         let address = "address";